@@ -0,0 +1,299 @@
+//! Searches files for byte patterns.
+//!
+//! Exits with 0 if anything matched, 1 if nothing did and 2 on errors.
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    process::ExitCode,
+};
+
+use patterns::{MultiScanner, Pattern};
+
+const USAGE: &str = "\
+usage: patterns scan [options] [file]...
+
+Reads stdin if no file or `-` is given.
+
+options:
+    -p, --pattern <pattern>    search a pattern like \"48 8b ? ?\", can be repeated
+    -f, --patterns-file <file> search every pattern in file, one per line
+    -c, --context <bytes>      print this many bytes around every match
+        --json                 print one JSON object per match
+    -h, --help                 print this help";
+
+#[derive(Debug, Default)]
+struct Options {
+    patterns: Vec<(String, Pattern)>,
+    files: Vec<String>,
+    context: Option<usize>,
+    json: bool,
+}
+
+/// Parses the arguments behind the binary name.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    if args.next().as_deref() != Some("scan") {
+        return Err(String::from("expected the `scan` command"));
+    }
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "-p" | "--pattern" => {
+                let pattern = value()?;
+                options.patterns.push(parse_pattern(pattern)?);
+            }
+            "-f" | "--patterns-file" => {
+                let path = value()?;
+                let patterns =
+                    std::fs::read_to_string(&path).map_err(|error| format!("{path}: {error}"))?;
+                for line in patterns.lines().map(str::trim) {
+                    if !line.is_empty() && !line.starts_with('#') {
+                        options.patterns.push(parse_pattern(line.to_owned())?);
+                    }
+                }
+            }
+            "-c" | "--context" => {
+                let context = value()?;
+                let context = context
+                    .parse()
+                    .map_err(|_| format!("invalid context: {context}"))?;
+                options.context = Some(context);
+            }
+            "--json" => options.json = true,
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option: {arg}"))
+            }
+            _ => options.files.push(arg),
+        }
+    }
+    if options.patterns.is_empty() {
+        return Err(String::from("expected at least one pattern"));
+    }
+    if options.files.is_empty() {
+        options.files.push(String::from("-"));
+    }
+    Ok(options)
+}
+
+fn parse_pattern(text: String) -> Result<(String, Pattern), String> {
+    match text.parse() {
+        Ok(pattern) => Ok((text, pattern)),
+        Err(error) => Err(format!("invalid pattern `{text}`: {error}")),
+    }
+}
+
+fn open(path: &str) -> io::Result<Box<dyn Read>> {
+    Ok(if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(File::open(path)?)
+    })
+}
+
+/// A match to print.
+struct Found<'data> {
+    pattern: usize,
+    offset: u64,
+    context: &'data [u8],
+}
+
+/// Searches one input and prints its matches, returns how many there were.
+fn scan(options: &Options, path: &str, out: &mut impl Write) -> io::Result<usize> {
+    let mut input = open(path)?;
+
+    // A single pattern without context streams through the input.
+    if let ([(_, pattern)], None) = (options.patterns.as_slice(), options.context) {
+        let mut found = 0;
+        for offset in pattern.matches_reader(input) {
+            let offset = offset?;
+            print(
+                options,
+                path,
+                &Found {
+                    pattern: 0,
+                    offset,
+                    context: &[],
+                },
+                out,
+            )?;
+            found += 1;
+        }
+        return Ok(found);
+    }
+
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+    let patterns = options
+        .patterns
+        .iter()
+        .map(|(_, pattern)| pattern.clone())
+        .collect::<Vec<_>>();
+    let mut matches = MultiScanner::new(&patterns, &data).collect::<Vec<_>>();
+    // Matches of different patterns arrive interleaved.
+    matches.sort_unstable_by_key(|&(pattern, offset)| (offset, pattern));
+    let context = options.context.unwrap_or(0);
+    for &(pattern, offset) in &matches {
+        let start = offset.saturating_sub(context);
+        let end = (offset + patterns[pattern].bytes().len())
+            .saturating_add(context)
+            .min(data.len());
+        let found = Found {
+            pattern,
+            offset: offset as u64,
+            context: if options.context.is_some() {
+                &data[start..end]
+            } else {
+                &[]
+            },
+        };
+        print(options, path, &found, out)?;
+    }
+    Ok(matches.len())
+}
+
+fn print(options: &Options, path: &str, found: &Found<'_>, out: &mut impl Write) -> io::Result<()> {
+    let pattern = &options.patterns[found.pattern].0;
+    let mut line = String::new();
+    if options.json {
+        line.push_str("{\"file\":");
+        json_string(&mut line, path);
+        line.push_str(",\"pattern\":");
+        json_string(&mut line, pattern);
+        let _ = write!(line, ",\"offset\":{}", found.offset);
+        if options.context.is_some() {
+            line.push_str(",\"context\":\"");
+            hex(&mut line, found.context, "");
+            line.push('"');
+        }
+        line.push('}');
+    } else {
+        let _ = write!(line, "{path}:{:#x}: {pattern}", found.offset);
+        if options.context.is_some() {
+            line.push_str(": ");
+            hex(&mut line, found.context, " ");
+        }
+    }
+    writeln!(out, "{line}")
+}
+
+fn hex(out: &mut String, bytes: &[u8], separator: &str) {
+    for (index, byte) in bytes.iter().enumerate() {
+        if index != 0 {
+            out.push_str(separator);
+        }
+        let _ = write!(out, "{byte:02x}");
+    }
+}
+
+fn json_string(out: &mut String, text: &str) {
+    out.push('"');
+    for char in text.chars() {
+        match char {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            char if char.is_control() => {
+                let _ = write!(out, "\\u{:04x}", u32::from(char));
+            }
+            char => out.push(char),
+        }
+    }
+    out.push('"');
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) if error.is_empty() => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut found = 0;
+    let mut failed = false;
+    for path in &options.files {
+        match scan(&options, path, &mut out) {
+            Ok(count) => found += count,
+            Err(error) => {
+                eprintln!("{path}: {error}");
+                failed = true;
+            }
+        }
+    }
+    if let Err(error) = out.flush() {
+        eprintln!("error: {error}");
+        failed = true;
+    }
+
+    if failed {
+        ExitCode::from(2)
+    } else if found == 0 {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| (*arg).to_owned()))
+    }
+
+    fn run(args: &[&str], data: &[u8]) -> String {
+        let options = options(args).unwrap();
+        let path = std::env::temp_dir().join(format!("patterns-cli-{}", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        let mut out = Vec::new();
+        scan(&options, path.to_str().unwrap(), &mut out).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .replace(path.to_str().unwrap(), "file")
+    }
+
+    #[test]
+    fn arguments() {
+        let parsed = options(&["scan", "-p", "01 ?", "--json", "a", "b"]).unwrap();
+        assert_eq!(parsed.patterns.len(), 1);
+        assert_eq!(parsed.files, ["a", "b"]);
+        assert!(parsed.json);
+        assert_eq!(options(&["scan", "-p", "01"]).unwrap().files, ["-"]);
+        assert!(options(&["scan", "a"]).is_err());
+        assert!(options(&["scan", "-p", "zz"]).is_err());
+        assert!(options(&["scan", "-p"]).is_err());
+        assert!(options(&["find", "-p", "01"]).is_err());
+    }
+
+    #[test]
+    fn output() {
+        let data = [0, 1, 2, 3, 1, 2];
+        assert_eq!(
+            run(&["scan", "-p", "01 02"], &data),
+            "file:0x1: 01 02\nfile:0x4: 01 02\n"
+        );
+        assert_eq!(
+            run(&["scan", "-p", "01 02", "-p", "02 03", "-c", "1"], &data),
+            "file:0x1: 01 02: 00 01 02 03\nfile:0x2: 02 03: 01 02 03 01\nfile:0x4: 01 02: 03 01 \
+             02\n"
+        );
+        assert_eq!(
+            run(&["scan", "-p", "02 03", "--json", "-c", "0"], &data),
+            "{\"file\":\"file\",\"pattern\":\"02 03\",\"offset\":2,\"context\":\"0203\"}\n"
+        );
+    }
+}
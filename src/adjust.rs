@@ -0,0 +1,111 @@
+use core::str::FromStr;
+
+use crate::{ParsePatternError, Pattern, Scanner};
+
+/// A pattern together with where the interesting position lies relative to
+/// the start of a match, e.g. the operand of a call instead of its opcode.
+///
+/// Parses the syntax of [`Pattern`] followed by an optional `+ n` or `- n`
+/// in decimal, the way signature lists usually write it.
+/// ```
+/// # use patterns::OffsetPattern;
+/// let pattern = OffsetPattern::new("e8 ? ? ? ? c3 + 1");
+/// let data = [0, 0xe8, 1, 2, 3, 4, 0xc3];
+/// assert_eq!(pattern.matches(&data).collect::<Vec<_>>(), [2]);
+/// assert_eq!(pattern.offset(), 1);
+///
+/// let invalid = "48 8b +x".parse::<OffsetPattern>().unwrap_err();
+/// assert!(invalid.to_string().starts_with("invalid offset"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct OffsetPattern {
+    pattern: Pattern,
+    offset: isize,
+}
+
+impl OffsetPattern {
+    /// Pairs a pattern with the offset added to every match.
+    #[inline]
+    pub const fn from_pattern(pattern: Pattern, offset: isize) -> Self {
+        Self { pattern, offset }
+    }
+
+    /// Parse a pattern. Use the [`FromStr`] impl to return an error instead of
+    /// panicking.
+    /// # Panics
+    /// Panics if [`ParsePatternError`] is returned.
+    #[inline]
+    pub fn new(pattern: &str) -> Self {
+        pattern.parse().unwrap()
+    }
+
+    /// The pattern without the offset.
+    #[inline]
+    pub const fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// The offset added to every match.
+    #[inline]
+    pub const fn offset(&self) -> isize {
+        self.offset
+    }
+
+    /// Creates an iterator through data that yields the match positions with
+    /// the offset added. Positions the offset moves outside of data are
+    /// skipped.
+    #[inline]
+    pub fn matches<'pattern, 'data>(
+        &'pattern self,
+        data: &'data [u8],
+    ) -> OffsetMatches<'pattern, 'data> {
+        OffsetMatches {
+            offset: self.offset,
+            len: data.len(),
+            scanner: self.pattern.matches(data),
+        }
+    }
+}
+
+impl FromStr for OffsetPattern {
+    type Err = ParsePatternError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(sign) = s.rfind(['+', '-']) else {
+            return Ok(Self::from_pattern(s.parse()?, 0));
+        };
+        let magnitude = s[sign + 1..]
+            .trim()
+            .parse::<isize>()
+            .map_err(ParsePatternError::InvalidOffset)?;
+        let offset = if s[sign..].starts_with('-') {
+            -magnitude
+        } else {
+            magnitude
+        };
+        Ok(Self::from_pattern(s[..sign].parse()?, offset))
+    }
+}
+
+/// An iterator through the adjusted positions of an [`OffsetPattern`], see
+/// [`OffsetPattern::matches`]
+#[must_use]
+pub struct OffsetMatches<'pattern, 'data> {
+    offset: isize,
+    len: usize,
+    scanner: Scanner<'pattern, 'data, 'data>,
+}
+
+impl<'pattern, 'data> Iterator for OffsetMatches<'pattern, 'data> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (offset, len) = (self.offset, self.len);
+        self.scanner.find_map(|index| {
+            let position = index.checked_add_signed(offset)?;
+            (position < len).then_some(position)
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{Pattern, Scanner, BYTES};
+
+impl Pattern {
+    /// Creates an iterator through data that only yields matches starting at
+    /// a multiple of `alignment` within data, e.g. headers at the start of a
+    /// page.
+    /// Alignments of at least [`BYTES`] skip the data between the aligned
+    /// offsets entirely and only compare the pattern there.
+    /// The alignment is a property of the scan, not of the pattern, so one
+    /// pattern serves aligned and unaligned scans alike.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("4d 5a");
+    /// let mut data = vec![0; 3 * 4096];
+    /// data[100..102].copy_from_slice(b"MZ");
+    /// data[4096..4098].copy_from_slice(b"MZ");
+    /// let found = pattern.matches_aligned(&data, 4096).collect::<Vec<_>>();
+    /// assert_eq!(found, [4096]);
+    /// ```
+    /// # Panics
+    /// Panics if `alignment` is 0.
+    #[inline]
+    pub fn matches_aligned<'pattern, 'data>(
+        &'pattern self,
+        data: &'data [u8],
+        alignment: usize,
+    ) -> Aligned<'pattern, 'data> {
+        assert!(alignment != 0, "alignment must not be 0");
+        let stride = alignment >= BYTES;
+        Aligned {
+            pattern: self,
+            data,
+            alignment,
+            stride,
+            next: 0,
+            scanner: self.matches(if stride { &data[..0] } else { data }),
+        }
+    }
+}
+
+/// An iterator through data that only yields aligned matches, see
+/// [`Pattern::matches_aligned`]
+#[must_use]
+pub struct Aligned<'pattern, 'data> {
+    pattern: &'pattern Pattern,
+    data: &'data [u8],
+    alignment: usize,
+    // Wide alignments check one offset per stride instead of scanning.
+    stride: bool,
+    // The next offset to check when striding.
+    next: usize,
+    // Narrow alignments filter the matches of a regular scan.
+    scanner: Scanner<'pattern, 'data, 'data>,
+}
+
+impl<'pattern, 'data> Iterator for Aligned<'pattern, 'data> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.stride {
+            loop {
+                let index = self.scanner.next()?;
+                let misalignment = index % self.alignment;
+                if misalignment == 0 {
+                    return Some(index);
+                }
+                self.scanner.skip_to(index - misalignment + self.alignment);
+            }
+        }
+        let anchor = self.pattern.first_offset;
+        let anchor_byte = self.pattern.first_byte[0];
+        while let Some(&byte) = self.data.get(self.next.saturating_add(anchor)) {
+            let index = self.next;
+            self.next = index.saturating_add(self.alignment);
+            // Only load the whole pattern if the anchor byte is there.
+            if byte == anchor_byte && self.pattern.matches_at(self.data, index) {
+                return Some(index);
+            }
+        }
+        None
+    }
+}
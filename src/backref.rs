@@ -0,0 +1,147 @@
+use core::str::FromStr;
+
+use crate::{ParsePatternError, Pattern, Scanner, BYTES};
+
+/// A pattern with bytes that have to repeat an earlier byte of the match, e.g.
+/// a mirrored header or a length that appears twice.
+///
+/// On top of the syntax of [`Pattern`], `$n` captures the byte at its position
+/// and `=n` only matches the same byte as capture n. Both match any byte
+/// otherwise, like wildcards. Captures are numbered from 0 to 9.
+/// Candidates are found with the plain pattern first, the references are
+/// checked after.
+/// ```
+/// # use patterns::BackRefPattern;
+/// let pattern = BackRefPattern::new("7e $1 ? =1 7e");
+/// let data = [0x7e, 5, 0, 5, 0x7e, 0x7e, 1, 0, 2, 0x7e];
+/// assert_eq!(pattern.matches(&data).collect::<Vec<_>>(), [0]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BackRefPattern {
+    pattern: Pattern,
+    // Pairs of positions whose bytes have to be equal, the reference first.
+    references: [(u8, u8); BYTES],
+    count: usize,
+    len: usize,
+}
+
+impl BackRefPattern {
+    /// Parse a pattern. Use the [`FromStr`] impl to return an error instead of
+    /// panicking.
+    /// # Panics
+    /// Panics if [`ParsePatternError`] is returned.
+    #[inline]
+    pub fn new(pattern: &str) -> Self {
+        pattern.parse().unwrap()
+    }
+
+    /// The pattern that finds the candidates, with wildcards at captures and
+    /// references.
+    #[inline]
+    pub const fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// Returns `true` if the pattern matches data at offset.
+    #[inline]
+    pub fn matches_at(&self, data: &[u8], offset: usize) -> bool {
+        match offset.checked_add(self.len) {
+            Some(end) if end <= data.len() => {
+                self.pattern.matches_at(data, offset) && self.check(&data[offset..])
+            }
+            _ => false,
+        }
+    }
+
+    /// Creates an iterator through data.
+    #[inline]
+    pub fn matches<'pattern, 'data>(
+        &'pattern self,
+        data: &'data [u8],
+    ) -> BackRefMatches<'pattern, 'data> {
+        BackRefMatches {
+            pattern: self,
+            data,
+            scanner: self.pattern.matches(data),
+        }
+    }
+
+    /// Checks the references of a match at the start of data.
+    fn check(&self, data: &[u8]) -> bool {
+        data.len() >= self.len
+            && self.references[..self.count]
+                .iter()
+                .all(|&(reference, capture)| {
+                    data[usize::from(reference)] == data[usize::from(capture)]
+                })
+    }
+}
+
+impl FromStr for BackRefPattern {
+    type Err = ParsePatternError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bytes = [0; BYTES];
+        let mut mask = 0_u64;
+        let mut captures = [None; 10];
+        let mut references = [(0, 0); BYTES];
+        let mut count = 0;
+        let mut len = 0;
+        for (index, token) in s.split_ascii_whitespace().enumerate() {
+            if index >= BYTES {
+                return Err(ParsePatternError::PatternTooLong);
+            }
+            let group = |digit: &str| {
+                digit
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&group| group < captures.len())
+                    .ok_or(ParsePatternError::InvalidBackReference)
+            };
+            if let Some(digit) = token.strip_prefix('$') {
+                captures[group(digit)?] = Some(index as u8);
+            } else if let Some(digit) = token.strip_prefix('=') {
+                let capture =
+                    captures[group(digit)?].ok_or(ParsePatternError::InvalidBackReference)?;
+                references[count] = (index as u8, capture);
+                count += 1;
+            } else if !token.starts_with(['?', '.']) {
+                bytes[index] = u8::from_str_radix(token, 16)?;
+                mask |= 1 << (u64::BITS as usize - 1 - index);
+            }
+            len = index + 1;
+        }
+        let pattern = Pattern::try_from_slice(&bytes[..len], mask)?;
+        // References can reach past the last non-wildcard byte.
+        let len = references[..count]
+            .iter()
+            .map(|&(reference, _)| usize::from(reference) + 1)
+            .fold(pattern.len(), usize::max);
+        Ok(Self {
+            pattern,
+            references,
+            count,
+            len,
+        })
+    }
+}
+
+/// An iterator through the matches of a [`BackRefPattern`], see
+/// [`BackRefPattern::matches`]
+#[must_use]
+pub struct BackRefMatches<'pattern, 'data> {
+    pattern: &'pattern BackRefPattern,
+    data: &'data [u8],
+    scanner: Scanner<'pattern, 'data, 'data>,
+}
+
+impl<'pattern, 'data> Iterator for BackRefMatches<'pattern, 'data> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (pattern, data) = (self.pattern, self.data);
+        self.scanner.find(|&index| pattern.check(&data[index..]))
+    }
+}
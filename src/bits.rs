@@ -0,0 +1,141 @@
+use core::array;
+
+use crate::{MultiScanner, ParsePatternError, Pattern, BYTES};
+
+/// A sequence of bits that can start at any bit offset in data, e.g. a sync
+/// word of a protocol that isn't byte aligned.
+/// Bits are numbered from the most significant bit of the first byte.
+///
+/// Expands to 8 byte patterns, one per bit shift, that are searched in a
+/// single pass with [`MultiScanner`]. Bytes only partially covered by the
+/// sequence are wildcards in those and checked bit by bit.
+/// ```
+/// # use patterns::BitPattern;
+/// assert!(BitPattern::new(&[0b1010_1100, 0b1111_0000], 12).is_err());
+/// let pattern = BitPattern::new(&[0b1010_1100, 0b1111_0000], 16).unwrap();
+/// let data = [0b0001_0101, 0b1001_1110, 0b0000_0000];
+/// assert_eq!(pattern.matches(&data).collect::<Vec<_>>(), [3]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BitPattern {
+    // The pattern for bit shift n starts n bits into its first byte.
+    patterns: [Pattern; 8],
+    bits: [u8; BYTES],
+    len: usize,
+}
+
+impl BitPattern {
+    /// The most bits a pattern can have, so that every shift fits into
+    /// [`BYTES`] bytes.
+    pub const MAX_BITS: usize = BYTES * 8 - 7;
+    /// The fewest bits a pattern can have, so that every shift covers at least
+    /// one whole byte.
+    pub const MIN_BITS: usize = 15;
+
+    /// Creates a pattern from the first `len` bits of `bits`.
+    /// # Errors
+    /// Returns [`ParsePatternError::MissingNonWildcardByte`] for fewer than
+    /// [`BitPattern::MIN_BITS`] bits and [`ParsePatternError::PatternTooLong`]
+    /// for more than [`BitPattern::MAX_BITS`].
+    /// # Panics
+    /// Panics if `bits` is shorter than `len` bits.
+    #[inline]
+    pub fn new(bits: &[u8], len: usize) -> Result<Self, ParsePatternError> {
+        assert!(len <= bits.len() * 8, "bits is shorter than {len} bits");
+        if len < Self::MIN_BITS {
+            return Err(ParsePatternError::MissingNonWildcardByte);
+        }
+        if len > Self::MAX_BITS {
+            return Err(ParsePatternError::PatternTooLong);
+        }
+        let mut buffer = [0; BYTES];
+        let bytes = len.div_ceil(8);
+        buffer[..bytes].copy_from_slice(&bits[..bytes]);
+        let patterns = array::from_fn(|shift| {
+            let mut shifted = [0; BYTES];
+            for index in 0..len {
+                if bit(&buffer, index) {
+                    let index = shift + index;
+                    shifted[index / 8] |= 0x80 >> (index % 8);
+                }
+            }
+            // Only bytes the sequence covers completely are compared.
+            let first = shift.div_ceil(8);
+            let end = (shift + len) / 8;
+            let mask = leading_bytes(end) & !leading_bytes(first);
+            Pattern::from_slice(&shifted[..end], mask)
+        });
+        Ok(Self {
+            patterns,
+            bits: buffer,
+            len,
+        })
+    }
+
+    /// The number of bits in the pattern.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Always `false`, a pattern has at least [`BitPattern::MIN_BITS`] bits.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Creates an iterator through data that yields the bit offset of every
+    /// match. Matches at different bit shifts can be interleaved, matches at
+    /// the same shift are yielded in ascending order.
+    #[inline]
+    pub fn matches<'pattern, 'data>(
+        &'pattern self,
+        data: &'data [u8],
+    ) -> BitMatches<'pattern, 'data> {
+        BitMatches {
+            pattern: self,
+            data,
+            scanner: MultiScanner::new(&self.patterns, data),
+        }
+    }
+
+    /// Compares the pattern with data at a bit offset.
+    fn verify(&self, data: &[u8], offset: usize) -> bool {
+        if offset + self.len > data.len() * 8 {
+            return false;
+        }
+        (0..self.len).all(|index| bit(data, offset + index) == bit(&self.bits, index))
+    }
+}
+
+/// An iterator through the bit offsets where a [`BitPattern`] matches, see
+/// [`BitPattern::matches`]
+#[must_use]
+pub struct BitMatches<'pattern, 'data> {
+    pattern: &'pattern BitPattern,
+    data: &'data [u8],
+    scanner: MultiScanner<'pattern, 'data>,
+}
+
+impl<'pattern, 'data> Iterator for BitMatches<'pattern, 'data> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.scanner.find_map(|(shift, index)| {
+            let offset = index * 8 + shift;
+            self.pattern.verify(self.data, offset).then_some(offset)
+        })
+    }
+}
+
+/// The bit at index, counting from the most significant bit of the first
+/// byte.
+fn bit(data: &[u8], index: usize) -> bool {
+    data[index / 8] & 0x80 >> (index % 8) != 0
+}
+
+/// A `from_slice` mask with the first `count` bytes set.
+fn leading_bytes(count: usize) -> u64 {
+    !u64::MAX.checked_shr(count as u32).unwrap_or(0)
+}
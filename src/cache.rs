@@ -0,0 +1,69 @@
+use core::ops::Range;
+use std::vec::Vec;
+
+use crate::Pattern;
+
+/// The matches of a pattern in data that changes over time, e.g. the memory
+/// of a live process.
+/// After data changed, only the changed ranges and the bytes around them
+/// that a match could overlap are searched again.
+/// ```
+/// # use patterns::{Pattern, ScanCache};
+/// let pattern = Pattern::new("01 02");
+/// let mut data = [0, 1, 2, 0, 0, 0, 1, 2];
+/// let mut cache = ScanCache::new(&pattern, &data);
+/// assert_eq!(cache.matches(), [1, 6]);
+///
+/// data[2] = 0;
+/// data[4] = 1;
+/// data[5] = 2;
+/// cache.update(&data, &[2..3, 4..6]);
+/// assert_eq!(cache.matches(), [4, 6]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScanCache<'pattern> {
+    pattern: &'pattern Pattern,
+    // Sorted ascending.
+    matches: Vec<usize>,
+}
+
+impl<'pattern> ScanCache<'pattern> {
+    /// Searches all of data once.
+    #[inline]
+    pub fn new(pattern: &'pattern Pattern, data: &[u8]) -> Self {
+        Self {
+            pattern,
+            matches: pattern.matches(data).collect(),
+        }
+    }
+
+    /// The offsets of all matches, in ascending order.
+    #[inline]
+    pub fn matches(&self) -> &[usize] {
+        &self.matches
+    }
+
+    /// Updates the matches after the `changed` ranges of data were written.
+    /// Data may have shrunk or grown since the last search, bytes appended at
+    /// the end have to be part of a changed range.
+    #[inline]
+    pub fn update(&mut self, data: &[u8], changed: &[Range<usize>]) {
+        let len = self.pattern.len();
+        let fits = data.len().saturating_sub(len - 1);
+        self.matches
+            .truncate(self.matches.partition_point(|&offset| offset < fits));
+        for range in changed {
+            // Matches starting in here overlap the changed bytes.
+            let start = range.start.saturating_sub(len - 1).min(fits);
+            let end = range.end.clamp(start, fits);
+            if start == end {
+                continue;
+            }
+            let first = self.matches.partition_point(|&offset| offset < start);
+            let last = self.matches.partition_point(|&offset| offset < end);
+            let window = &data[start..end + len - 1];
+            let found = self.pattern.matches(window).map(|offset| start + offset);
+            self.matches.splice(first..last, found);
+        }
+    }
+}
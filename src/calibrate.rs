@@ -0,0 +1,41 @@
+use core::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::{Pattern, Prefilter};
+
+/// How often every prefilter scans the sample. The fastest run counts.
+const RUNS: usize = 3;
+
+impl Pattern {
+    /// Picks the [`Prefilter`] that scans `sample` the fastest on this
+    /// machine.
+    /// `sample` should look like the data the pattern is meant for, a few
+    /// megabytes are enough. Meant to be called once at startup by
+    /// long-running scanners, it scans the sample several times.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let sample = vec![0; 1 << 16];
+    /// let pattern = Pattern::new("00 ? ? 01").calibrate(&sample);
+    /// assert_eq!(pattern.matches(&sample).count(), 0);
+    /// ```
+    #[inline]
+    pub fn calibrate(self, sample: &[u8]) -> Self {
+        [Prefilter::FirstByte, Prefilter::TwoBytes]
+            .into_iter()
+            .map(|prefilter| self.clone().with_prefilter(prefilter))
+            .min_by_key(|pattern| pattern.time(sample))
+            .unwrap_or(self)
+    }
+
+    /// The fastest of a few scans through sample.
+    fn time(&self, sample: &[u8]) -> Duration {
+        (0..RUNS)
+            .map(|_| {
+                let start = Instant::now();
+                black_box(self.matches(black_box(sample)).count());
+                start.elapsed()
+            })
+            .min()
+            .unwrap_or_default()
+    }
+}
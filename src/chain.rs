@@ -0,0 +1,198 @@
+use crate::{Pattern, Scanner};
+
+impl Pattern {
+    /// Creates an iterator through data that pairs every match of this pattern
+    /// with the first match of `other` after it.
+    /// `other` has to start after the last non-wildcard byte of this pattern,
+    /// with at most `max_distance` bytes in between. Yields
+    /// `(offset, other_offset)` pairs.
+    /// ```
+    /// # use patterns::Pattern;
+    /// // A string reference followed by a call.
+    /// let lea = Pattern::new("48 8D 0D ? ? ? ?");
+    /// let call = Pattern::new("E8 ? ? ? ?");
+    /// let code = [
+    ///     0x48, 0x8D, 0x0D, 1, 2, 3, 4, 0x90, 0xE8, 1, 2, 3, 4, //
+    ///     0x48, 0x8D, 0x0D, 1, 2, 3, 4,
+    /// ];
+    /// let found = lea.then(&call, 8).matches(&code).collect::<Vec<_>>();
+    /// assert_eq!(found, [(0, 8)]);
+    /// ```
+    #[inline]
+    pub const fn then<'pattern>(
+        &'pattern self,
+        other: &'pattern Pattern,
+        max_distance: usize,
+    ) -> Chain<'pattern> {
+        Chain {
+            first: self,
+            second: other,
+            max_distance,
+        }
+    }
+
+    /// Creates a search for positions where this pattern and `other` start at
+    /// most `distance` bytes apart, in either order.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let first = Pattern::new("01 01");
+    /// let second = Pattern::new("02");
+    /// let data = [2, 0, 1, 1, 0, 0, 0, 0, 1, 1, 0, 2];
+    /// let found = first.near(&second, 2).matches(&data).collect::<Vec<_>>();
+    /// assert_eq!(found, [(2, 0)]);
+    /// ```
+    #[inline]
+    pub const fn near<'pattern>(
+        &'pattern self,
+        other: &'pattern Pattern,
+        distance: usize,
+    ) -> Proximity<'pattern> {
+        Proximity {
+            first: self,
+            second: other,
+            distance,
+        }
+    }
+}
+
+/// Two patterns that have to occur after each other, see [`Pattern::then`]
+#[derive(Clone, Copy, Debug)]
+pub struct Chain<'pattern> {
+    first: &'pattern Pattern,
+    second: &'pattern Pattern,
+    max_distance: usize,
+}
+
+impl<'pattern> Chain<'pattern> {
+    /// Creates an iterator through data.
+    /// Both patterns are scanned in a single pass each.
+    #[inline]
+    pub fn matches<'data>(&self, data: &'data [u8]) -> ChainScanner<'pattern, 'data> {
+        ChainScanner {
+            first: self.first.matches(data),
+            second: self.second.matches(data),
+            first_len: self.first.len(),
+            max_distance: self.max_distance,
+            pending: None,
+        }
+    }
+}
+
+/// An iterator through data searching a [`Chain`]
+#[must_use]
+pub struct ChainScanner<'pattern, 'data> {
+    first: Scanner<'pattern, 'data, 'data>,
+    second: Scanner<'pattern, 'data, 'data>,
+    first_len: usize,
+    max_distance: usize,
+    // The last match of the second pattern.
+    pending: Option<usize>,
+}
+
+impl<'pattern, 'data> Iterator for ChainScanner<'pattern, 'data> {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let first = self.first.next()?;
+            let end = first + self.first_len;
+            let second = match self.pending {
+                Some(second) if second >= end => second,
+                _ => {
+                    self.second.skip_to(end);
+                    // Without any match of the second pattern left, there are no
+                    // more pairs.
+                    let second = self.second.next()?;
+                    self.pending = Some(second);
+                    second
+                }
+            };
+            if second - end <= self.max_distance {
+                return Some((first, second));
+            }
+        }
+    }
+}
+
+/// Two patterns that have to occur close to each other, see
+/// [`Pattern::near`]
+#[derive(Clone, Copy, Debug)]
+pub struct Proximity<'pattern> {
+    first: &'pattern Pattern,
+    second: &'pattern Pattern,
+    distance: usize,
+}
+
+impl<'pattern> Proximity<'pattern> {
+    /// Creates an iterator through data yielding `(offset, other_offset)`
+    /// pairs. Every match is paired with the closest match of the other
+    /// pattern before it. Both patterns are scanned side by side in a single
+    /// pass.
+    #[inline]
+    pub fn matches<'data>(&self, data: &'data [u8]) -> ProximityScanner<'pattern, 'data> {
+        let mut first = self.first.matches(data);
+        let mut second = self.second.matches(data);
+        ProximityScanner {
+            next_first: first.next(),
+            next_second: second.next(),
+            first,
+            second,
+            last_first: None,
+            last_second: None,
+            distance: self.distance,
+        }
+    }
+}
+
+/// An iterator through data searching a [`Proximity`]
+#[must_use]
+pub struct ProximityScanner<'pattern, 'data> {
+    first: Scanner<'pattern, 'data, 'data>,
+    second: Scanner<'pattern, 'data, 'data>,
+    next_first: Option<usize>,
+    next_second: Option<usize>,
+    last_first: Option<usize>,
+    last_second: Option<usize>,
+    distance: usize,
+}
+
+impl<'pattern, 'data> Iterator for ProximityScanner<'pattern, 'data> {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        // Takes matches of both patterns in ascending order and pairs each with
+        // the last match of the other one.
+        loop {
+            let take_first = match (self.next_first, self.next_second) {
+                (Some(first), Some(second)) => first <= second,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return None,
+            };
+            if take_first {
+                let first = self.next_first.take()?;
+                self.next_first = self.first.next();
+                self.last_first = Some(first);
+                match self.last_second {
+                    Some(second) if first - second <= self.distance => {
+                        return Some((first, second))
+                    }
+                    // Nothing left to pair with.
+                    _ if self.next_second.is_none() => return None,
+                    _ => {}
+                }
+            } else {
+                let second = self.next_second.take()?;
+                self.next_second = self.second.next();
+                self.last_second = Some(second);
+                match self.last_first {
+                    Some(first) if second - first <= self.distance => return Some((first, second)),
+                    _ if self.next_first.is_none() => return None,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
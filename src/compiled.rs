@@ -0,0 +1,213 @@
+use core::{error::Error, fmt, mem};
+
+use crate::{
+    simd::{Mask, Simd},
+    Pattern, Prefilter, BYTES,
+};
+
+const MAGIC: [u8; 4] = *b"PTRN";
+
+/// A pattern in a fixed binary layout that can be baked into a program, e.g.
+/// written by a build script and embedded with `include_bytes!`.
+/// Loading it only validates the layout, the anchors picked when the pattern
+/// was compiled are kept, including ones from [`Pattern::with_frequencies`].
+///
+/// The layout of version 1 is [`CompiledPattern::SIZE`] bytes:
+///
+/// | offset | size | content |
+/// |-------:|-----:|---------|
+/// | 0 | 4 | `PTRN` |
+/// | 4 | 1 | version |
+/// | 5 | 1 | length up to the last non-wildcard byte |
+/// | 6 | 1 | offset of the first anchor |
+/// | 7 | 1 | offset of the second anchor |
+/// | 8 | 1 | prefilter, 0 for one byte and 1 for two bytes |
+/// | 9 | 7 | zero |
+/// | 16 | 8 | mask, little endian, bit n set if byte n is no wildcard |
+/// | 24 | 64 | bytes, zero at wildcards and past the length |
+/// ```
+/// # use patterns::{CompiledPattern, Pattern};
+/// static COMPILED: [u8; CompiledPattern::SIZE] = {
+///     // Usually generated at build time with `Pattern::compile`.
+///     let mut compiled = [0; CompiledPattern::SIZE];
+///     let header = [b'P', b'T', b'R', b'N', 1, 3, 2, 0, 0];
+///     let mut index = 0;
+///     while index < header.len() {
+///         compiled[index] = header[index];
+///         index += 1;
+///     }
+///     compiled[16] = 0b101;
+///     compiled[24] = 0x01;
+///     compiled[26] = 0x03;
+///     compiled
+/// };
+/// let compiled = CompiledPattern::from_bytes(&COMPILED).unwrap();
+/// assert_eq!(compiled, &Pattern::new("01 ? 03").compile());
+/// let pattern = compiled.to_pattern();
+/// assert_eq!(pattern.find(&[0, 1, 2, 3]), Some(1));
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CompiledPattern {
+    magic: [u8; 4],
+    version: u8,
+    len: u8,
+    first_offset: u8,
+    second_offset: u8,
+    prefilter: u8,
+    reserved: [u8; 7],
+    mask: [u8; 8],
+    bytes: [u8; BYTES],
+}
+
+impl CompiledPattern {
+    /// The size of the layout in bytes.
+    pub const SIZE: usize = mem::size_of::<Self>();
+    /// The version of the layout this crate writes and reads.
+    pub const VERSION: u8 = 1;
+
+    /// Validates a compiled pattern in place, without copying it.
+    /// ```
+    /// # use patterns::{CompiledPattern, CompiledPatternError, Pattern};
+    /// let mut bytes = *Pattern::new("01 02 03").compile().as_bytes();
+    /// // Both anchors on the same byte of a pattern with three of them.
+    /// bytes[7] = bytes[6];
+    /// assert_eq!(
+    ///     CompiledPattern::from_bytes(&bytes),
+    ///     Err(CompiledPatternError::Invalid)
+    /// );
+    /// ```
+    /// # Errors
+    /// Returns an error if bytes isn't exactly [`CompiledPattern::SIZE`] long
+    /// or doesn't hold a valid pattern of [`CompiledPattern::VERSION`].
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<&Self, CompiledPatternError> {
+        if bytes.len() != Self::SIZE {
+            return Err(CompiledPatternError::WrongSize);
+        }
+        // Safety: the layout consists of byte arrays only, so every byte
+        // sequence of the right length is a value and alignment is 1.
+        let compiled = unsafe { &*bytes.as_ptr().cast::<Self>() };
+        compiled.validate()?;
+        Ok(compiled)
+    }
+
+    /// The compiled pattern as bytes, to save it.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8; Self::SIZE] {
+        // Safety: the layout consists of byte arrays only and has no padding.
+        unsafe { &*(self as *const Self).cast() }
+    }
+
+    /// Loads the pattern into the registers the search uses.
+    #[inline]
+    pub fn to_pattern(&self) -> Pattern {
+        let bytes = Simd::from_array(self.bytes);
+        let first_offset = usize::from(self.first_offset);
+        let second_offset = usize::from(self.second_offset);
+        Pattern {
+            bytes,
+            mask: Mask::from_bitmask(u64::from_le_bytes(self.mask)),
+            first_offset,
+            first_byte: Simd::splat(self.bytes[first_offset]),
+            second_offset,
+            second_byte: self.bytes[second_offset],
+            prefilter: if self.prefilter == 1 {
+                Prefilter::TwoBytes
+            } else {
+                Prefilter::FirstByte
+            },
+        }
+    }
+
+    fn validate(&self) -> Result<(), CompiledPatternError> {
+        if self.magic != MAGIC {
+            return Err(CompiledPatternError::WrongMagic);
+        }
+        if self.version != Self::VERSION {
+            return Err(CompiledPatternError::UnsupportedVersion);
+        }
+        let len = usize::from(self.len);
+        let mask = u64::from_le_bytes(self.mask);
+        let has = |offset: u8| mask & 1_u64.checked_shl(u32::from(offset)).unwrap_or(0) != 0;
+        // The search skips verifying the other bytes if both anchors are the
+        // same, which only holds for a single non-wildcard byte.
+        let anchors = (self.first_offset != self.second_offset) == (mask.count_ones() > 1);
+        let valid = (1..=BYTES).contains(&len)
+            && mask >> (len - 1) == 1
+            && usize::from(self.first_offset) < len
+            && usize::from(self.second_offset) < len
+            && has(self.first_offset)
+            && has(self.second_offset)
+            && anchors
+            && self.prefilter <= 1
+            && self.reserved == [0; 7]
+            && self
+                .bytes
+                .iter()
+                .enumerate()
+                .all(|(index, &byte)| byte == 0 || mask & 1 << index != 0);
+        if valid {
+            Ok(())
+        } else {
+            Err(CompiledPatternError::Invalid)
+        }
+    }
+}
+
+impl Pattern {
+    /// Writes the pattern in the layout of [`CompiledPattern`].
+    #[inline]
+    pub fn compile(&self) -> CompiledPattern {
+        let mut bytes = [0; BYTES];
+        let len = self.len();
+        bytes[..len].copy_from_slice(self.bytes());
+        CompiledPattern {
+            magic: MAGIC,
+            version: CompiledPattern::VERSION,
+            // Everything fits into a byte, patterns are at most 64 bytes long.
+            len: len as u8,
+            first_offset: self.first_offset as u8,
+            second_offset: self.second_offset as u8,
+            prefilter: match self.prefilter {
+                Prefilter::TwoBytes => 1,
+                Prefilter::FirstByte => 0,
+            },
+            reserved: [0; 7],
+            mask: self.mask.to_bitmask().to_le_bytes(),
+            bytes,
+        }
+    }
+}
+
+/// The reasons a [`CompiledPattern`] can fail to load.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompiledPatternError {
+    WrongSize,
+    WrongMagic,
+    UnsupportedVersion,
+    Invalid,
+}
+
+impl fmt::Display for CompiledPatternError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongSize => write!(
+                f,
+                "compiled pattern is not {} bytes long",
+                CompiledPattern::SIZE
+            ),
+            Self::WrongMagic => f.write_str("not a compiled pattern"),
+            Self::UnsupportedVersion => write!(
+                f,
+                "compiled pattern is not version {}",
+                CompiledPattern::VERSION
+            ),
+            Self::Invalid => f.write_str("compiled pattern is corrupted"),
+        }
+    }
+}
+
+impl Error for CompiledPatternError {}
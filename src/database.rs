@@ -0,0 +1,216 @@
+use core::{error::Error, fmt, str::FromStr};
+use std::{string::String, vec::Vec};
+
+use crate::{MultiScanner, ParsePatternError, Pattern, PatternSet};
+
+/// A list of named patterns read from a text file, e.g. the signatures of a
+/// tool.
+///
+/// Every line holds a name, a colon and a pattern. Options follow after a
+/// `|`: `align=N` only accepts matches at multiples of N and `offset=N` moves
+/// the reported position by N bytes, e.g. to the operand of an instruction.
+/// Empty lines and lines starting with `#` are ignored.
+/// [`Display`](fmt::Display) writes the same format.
+/// ```
+/// # use patterns::PatternDatabase;
+/// let database: PatternDatabase = "
+///     ## player data
+///     health: 8b 81 ? ? ? ? 85 c0
+///     tick: e8 ? 90 | align=2 offset=1
+/// "
+/// .parse()
+/// .unwrap();
+/// assert_eq!(database.len(), 2);
+///
+/// let data = [0, 0xe8, 1, 0x90, 0, 0, 0xe8, 2, 0x90];
+/// let found = database.scan(&data).collect::<Vec<_>>();
+/// assert_eq!(found, [("tick", 7)]);
+/// assert_eq!(
+///     database.to_string(),
+///     "health: 8b 81 ? ? ? ? 85 c0\ntick: e8 ? 90 | align=2 offset=1\n"
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PatternDatabase {
+    names: Vec<String>,
+    patterns: Vec<Pattern>,
+    alignments: Vec<usize>,
+    offsets: Vec<isize>,
+}
+
+impl PatternDatabase {
+    /// Creates an empty database.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            names: Vec::new(),
+            patterns: Vec::new(),
+            alignments: Vec::new(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Adds a pattern. An alignment of 1 and an offset of 0 are the defaults.
+    /// # Panics
+    /// Panics if `alignment` is 0.
+    #[inline]
+    pub fn push(&mut self, name: String, pattern: Pattern, alignment: usize, offset: isize) {
+        assert!(alignment != 0, "alignment must not be 0");
+        self.names.push(name);
+        self.patterns.push(pattern);
+        self.alignments.push(alignment);
+        self.offsets.push(offset);
+    }
+
+    /// The number of patterns in the database.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns `true` if the database has no patterns.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Iterates over the name, pattern, alignment and offset of every entry.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Pattern, usize, isize)> {
+        self.names
+            .iter()
+            .zip(&self.patterns)
+            .zip(self.alignments.iter().zip(&self.offsets))
+            .map(|((name, pattern), (&alignment, &offset))| {
+                (name.as_str(), pattern, alignment, offset)
+            })
+    }
+
+    /// The patterns with their names as ids, ignoring alignments and offsets.
+    #[inline]
+    pub fn set(&self) -> PatternSet<'_, String> {
+        PatternSet::new(&self.names, &self.patterns)
+    }
+
+    /// Searches all patterns in a single pass and yields their names and
+    /// positions, with alignments and offsets applied. Positions an offset
+    /// moves outside of data are skipped.
+    #[inline]
+    pub fn scan<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = (&'a str, usize)> {
+        let len = data.len();
+        MultiScanner::new(&self.patterns, data).filter_map(move |(entry, index)| {
+            if index % self.alignments[entry] != 0 {
+                return None;
+            }
+            let position = index.checked_add_signed(self.offsets[entry])?;
+            (position < len).then_some((self.names[entry].as_str(), position))
+        })
+    }
+}
+
+impl FromStr for PatternDatabase {
+    type Err = ParseDatabaseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut database = Self::new();
+        for (index, line) in s.lines().enumerate() {
+            let error = |reason| ParseDatabaseError {
+                line: index + 1,
+                reason,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, rest) = line
+                .split_once(':')
+                .ok_or(error(DatabaseErrorReason::MissingName))?;
+            let name = name.trim();
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(error(DatabaseErrorReason::MissingName));
+            }
+            let (pattern, options) = rest.split_once('|').unwrap_or((rest, ""));
+            let pattern = pattern
+                .parse()
+                .map_err(|pattern| error(DatabaseErrorReason::InvalidPattern(pattern)))?;
+            let mut alignment = 1;
+            let mut offset = 0;
+            for option in options.split_ascii_whitespace() {
+                let parsed = match option.split_once('=') {
+                    Some(("align", value)) => value
+                        .parse()
+                        .ok()
+                        .filter(|&value| value != 0)
+                        .map(|value| alignment = value),
+                    Some(("offset", value)) => value.parse().ok().map(|value| offset = value),
+                    _ => None,
+                };
+                parsed.ok_or(error(DatabaseErrorReason::InvalidOption))?;
+            }
+            database.push(name.into(), pattern, alignment, offset);
+        }
+        Ok(database)
+    }
+}
+
+impl fmt::Display for PatternDatabase {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, pattern, alignment, offset) in self.iter() {
+            write!(f, "{name}: {pattern}")?;
+            if alignment != 1 || offset != 0 {
+                f.write_str(" |")?;
+            }
+            if alignment != 1 {
+                write!(f, " align={alignment}")?;
+            }
+            if offset != 0 {
+                write!(f, " offset={offset}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The reason and line a [`PatternDatabase`] failed to parse at.
+#[derive(Debug)]
+pub struct ParseDatabaseError {
+    /// The line, starting at 1
+    pub line: usize,
+    pub reason: DatabaseErrorReason,
+}
+
+/// The reasons a line of a [`PatternDatabase`] can fail to parse.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DatabaseErrorReason {
+    MissingName,
+    InvalidPattern(ParsePatternError),
+    InvalidOption,
+}
+
+impl fmt::Display for ParseDatabaseError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.reason {
+            DatabaseErrorReason::MissingName => f.write_str("expected `name: pattern`"),
+            DatabaseErrorReason::InvalidPattern(error) => write!(f, "{error}"),
+            DatabaseErrorReason::InvalidOption => {
+                f.write_str("expected `align=N` or `offset=N` options")
+            }
+        }
+    }
+}
+
+impl Error for ParseDatabaseError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.reason {
+            DatabaseErrorReason::InvalidPattern(error) => Some(error),
+            _ => None,
+        }
+    }
+}
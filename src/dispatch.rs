@@ -0,0 +1,195 @@
+use core::{
+    ops::ControlFlow,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::{swar, Adaptive, Pattern};
+
+/// The instruction set the scanner runs on.
+/// Picked at runtime from the features of the CPU, so binaries built for a
+/// baseline target still use wide vector instructions where they are
+/// available. Without the `std` feature, only the target features enabled at
+/// compile time are considered.
+/// ```
+/// # use patterns::Dispatch;
+/// let dispatch = Dispatch::current();
+/// assert_eq!(dispatch, Dispatch::current());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum Dispatch {
+    /// AVX-512 with byte and word instructions, BMI1 and BMI2
+    Avx512 = 1,
+    /// AVX2, BMI1 and BMI2
+    Avx2,
+    /// SSE4.2
+    Sse42,
+    /// NEON, part of every aarch64 target with floating point. Only picked if
+    /// it is enabled at compile time, so it runs the same code as
+    /// [`Dispatch::Generic`]
+    Neon,
+    /// Whatever the target features enabled at compile time allow
+    Generic,
+    /// Scalar code working on 64 bit words, for targets without vector
+    /// registers
+    Swar,
+}
+
+// 0 until the first detection.
+static CACHED: AtomicU8 = AtomicU8::new(0);
+
+impl Dispatch {
+    /// The instruction set of the current CPU. Detected on first use and
+    /// cached afterwards.
+    #[inline]
+    pub fn current() -> Self {
+        match CACHED.load(Ordering::Relaxed) {
+            1 => Self::Avx512,
+            2 => Self::Avx2,
+            3 => Self::Sse42,
+            4 => Self::Neon,
+            5 => Self::Generic,
+            6 => Self::Swar,
+            _ => {
+                let dispatch = Self::detect();
+                CACHED.store(dispatch as u8, Ordering::Relaxed);
+                dispatch
+            }
+        }
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn detect() -> Self {
+        let bmi = detected!("bmi1") && detected!("bmi2");
+        if detected!("avx512bw") && bmi {
+            Self::Avx512
+        } else if detected!("avx2") && bmi {
+            Self::Avx2
+        } else if detected!("sse4.2") {
+            Self::Sse42
+        } else {
+            Self::fallback()
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    fn detect() -> Self {
+        // Soft float targets come without it.
+        if cfg!(target_feature = "neon") {
+            Self::Neon
+        } else {
+            Self::fallback()
+        }
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    fn detect() -> Self {
+        Self::fallback()
+    }
+
+    /// The generic code if `core::simd` has vector registers to work with.
+    const fn fallback() -> Self {
+        if swar::HAS_SIMD {
+            Self::Generic
+        } else {
+            Self::Swar
+        }
+    }
+}
+
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+macro_rules! detected {
+    ($feature:tt) => {
+        std::is_x86_feature_detected!($feature)
+    };
+}
+
+#[cfg(all(not(feature = "std"), any(target_arch = "x86", target_arch = "x86_64")))]
+macro_rules! detected {
+    ($feature:tt) => {
+        cfg!(target_feature = $feature)
+    };
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use detected;
+
+/// Runs [`crate::fold_chunks`] compiled for the instruction set of the
+/// current CPU.
+#[inline]
+pub(crate) fn fold_chunks<B, T>(
+    pattern: &Pattern,
+    haystack: &[u8],
+    cursor: &mut usize,
+    streaming: bool,
+    adaptive: &mut Adaptive,
+    init: B,
+    f: impl FnMut(B, usize, u64) -> ControlFlow<T, B>,
+) -> ControlFlow<T, B> {
+    // Nothing to pick if the widest instructions are enabled at compile time.
+    // Miri can't run code for target features it doesn't know to be enabled.
+    if cfg!(any(target_feature = "avx512bw", miri)) {
+        return crate::fold_chunks(pattern, haystack, cursor, streaming, adaptive, init, f);
+    }
+    match Dispatch::current() {
+        // Safety: The CPU supports the target features of each variant.
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Dispatch::Avx512 => unsafe {
+            fold_avx512(pattern, haystack, cursor, streaming, adaptive, init, f)
+        },
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Dispatch::Avx2 => unsafe {
+            fold_avx2(pattern, haystack, cursor, streaming, adaptive, init, f)
+        },
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Dispatch::Sse42 => unsafe {
+            fold_sse42(pattern, haystack, cursor, streaming, adaptive, init, f)
+        },
+        Dispatch::Swar => swar::fold_chunks(pattern, haystack, cursor, adaptive, init, f),
+        // NEON is enabled at compile time if it was detected.
+        _ => crate::fold_chunks(pattern, haystack, cursor, streaming, adaptive, init, f),
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx512bw,bmi1,bmi2")]
+fn fold_avx512<B, T>(
+    pattern: &Pattern,
+    haystack: &[u8],
+    cursor: &mut usize,
+    streaming: bool,
+    adaptive: &mut Adaptive,
+    init: B,
+    f: impl FnMut(B, usize, u64) -> ControlFlow<T, B>,
+) -> ControlFlow<T, B> {
+    crate::fold_chunks(pattern, haystack, cursor, streaming, adaptive, init, f)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2,bmi1,bmi2")]
+fn fold_avx2<B, T>(
+    pattern: &Pattern,
+    haystack: &[u8],
+    cursor: &mut usize,
+    streaming: bool,
+    adaptive: &mut Adaptive,
+    init: B,
+    f: impl FnMut(B, usize, u64) -> ControlFlow<T, B>,
+) -> ControlFlow<T, B> {
+    crate::fold_chunks(pattern, haystack, cursor, streaming, adaptive, init, f)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse4.2")]
+fn fold_sse42<B, T>(
+    pattern: &Pattern,
+    haystack: &[u8],
+    cursor: &mut usize,
+    streaming: bool,
+    adaptive: &mut Adaptive,
+    init: B,
+    f: impl FnMut(B, usize, u64) -> ControlFlow<T, B>,
+) -> ControlFlow<T, B> {
+    crate::fold_chunks(pattern, haystack, cursor, streaming, adaptive, init, f)
+}
@@ -0,0 +1,171 @@
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+use crate::{
+    load,
+    simd::{Simd, SimdPartialEq},
+    ParsePatternError, Pattern, Scanner, BYTES,
+};
+
+/// A pattern of any length, stored on the heap.
+/// The first [`BYTES`] bytes chunk with a non-wildcard byte finds the
+/// candidates like a [`Pattern`], the whole needle is then compared one chunk
+/// at a time.
+/// ```
+/// # use patterns::DynLenPattern;
+/// let needle = vec!["aa"; 100].join(" ") + " ? bb";
+/// let pattern: DynLenPattern = needle.parse().unwrap();
+/// assert_eq!(pattern.len(), 102);
+/// let mut data = vec![0; 300];
+/// data[50..150].fill(0xaa);
+/// data[151] = 0xbb;
+/// assert_eq!(pattern.matches(&data).collect::<Vec<_>>(), [50]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DynLenPattern {
+    // Finds the candidates, it starts `anchor_offset` bytes into the needle.
+    anchor: Pattern,
+    anchor_offset: usize,
+    // The needle in chunks of `BYTES` bytes and their masks, bit n is set if
+    // byte n of the chunk is no wildcard.
+    chunks: Vec<(Simd<u8, BYTES>, u64)>,
+    len: usize,
+}
+
+impl DynLenPattern {
+    /// Parse a pattern. Use the [`FromStr`] impl to return an error instead
+    /// of panicking.
+    /// # Panics
+    /// Panics if [`ParsePatternError`] is returned.
+    #[inline]
+    pub fn new(pattern: &str) -> Self {
+        pattern.parse().unwrap()
+    }
+
+    /// Create a pattern from one optional byte per position, `None` is a
+    /// wildcard.
+    /// # Errors
+    /// Returns an error if all bytes are wildcards.
+    #[inline]
+    pub fn from_options(needle: &[Option<u8>]) -> Result<Self, ParsePatternError> {
+        let len = needle
+            .iter()
+            .rposition(Option::is_some)
+            .ok_or(ParsePatternError::MissingNonWildcardByte)?
+            + 1;
+        let chunks = needle[..len]
+            .chunks(BYTES)
+            .map(|chunk| {
+                let mut bytes = [0; BYTES];
+                let mut mask = 0_u64;
+                for (index, byte) in chunk.iter().enumerate() {
+                    if let Some(byte) = byte {
+                        bytes[index] = *byte;
+                        mask |= 1 << index;
+                    }
+                }
+                (Simd::from_array(bytes), mask)
+            })
+            .collect::<Vec<_>>();
+        let (index, &(bytes, mask)) = chunks
+            .iter()
+            .enumerate()
+            .find(|(_, (_, mask))| *mask != 0)
+            .ok_or(ParsePatternError::MissingNonWildcardByte)?;
+        let anchor_len = (len - index * BYTES).min(BYTES);
+        let anchor = Pattern::from_slice(&bytes.as_array()[..anchor_len], mask.reverse_bits());
+        Ok(Self {
+            anchor,
+            anchor_offset: index * BYTES,
+            chunks,
+            len,
+        })
+    }
+
+    /// The number of bytes up to the last non-wildcard byte.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Always `false`, a pattern has at least one non-wildcard byte.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the pattern matches data at offset.
+    #[inline]
+    pub fn matches_at(&self, data: &[u8], offset: usize) -> bool {
+        match offset.checked_add(self.len) {
+            Some(end) if end <= data.len() => self.verify(data, offset),
+            _ => false,
+        }
+    }
+
+    /// Creates an iterator through data.
+    #[inline]
+    pub fn matches<'pattern, 'data>(
+        &'pattern self,
+        data: &'data [u8],
+    ) -> DynLenMatches<'pattern, 'data> {
+        DynLenMatches {
+            pattern: self,
+            data,
+            scanner: self.anchor.matches(data),
+        }
+    }
+
+    /// Compares all chunks, the whole needle has to fit into data.
+    fn verify(&self, data: &[u8], offset: usize) -> bool {
+        self.chunks
+            .iter()
+            .enumerate()
+            .all(|(index, &(bytes, mask))| {
+                let equal = load(&data[offset + index * BYTES..]).simd_eq(bytes);
+                equal.to_bitmask() & mask == mask
+            })
+    }
+}
+
+impl FromStr for DynLenPattern {
+    type Err = ParsePatternError;
+
+    /// Parses the syntax of [`Pattern`] without its length limit.
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let needle = s
+            .split_ascii_whitespace()
+            .map(|byte| {
+                if byte.starts_with(['?', '.']) {
+                    Ok(None)
+                } else {
+                    u8::from_str_radix(byte, 16).map(Some)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_options(&needle)
+    }
+}
+
+/// An iterator through the matches of a [`DynLenPattern`], see
+/// [`DynLenPattern::matches`]
+#[must_use]
+pub struct DynLenMatches<'pattern, 'data> {
+    pattern: &'pattern DynLenPattern,
+    data: &'data [u8],
+    scanner: Scanner<'pattern, 'data, 'data>,
+}
+
+impl<'pattern, 'data> Iterator for DynLenMatches<'pattern, 'data> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let pattern = self.pattern;
+        self.scanner.find_map(|index| {
+            let offset = index.checked_sub(pattern.anchor_offset)?;
+            pattern.matches_at(self.data, offset).then_some(offset)
+        })
+    }
+}
@@ -0,0 +1,154 @@
+use core::{marker::PhantomData, mem, slice, str::FromStr};
+
+use crate::{Aligned, ParsePatternError, Pattern, BYTES};
+
+/// An integer type that [`ElementPattern`] can search for.
+/// Implemented for `u8`, `u16`, `u32` and `u64`.
+pub trait Element: Copy + private::Sealed {}
+
+mod private {
+    pub trait Sealed {
+        /// Writes the native endian bytes of the value to the start of out.
+        fn write(self, out: &mut [u8]);
+        fn from_hex(hex: &str) -> Result<Self, core::num::ParseIntError>
+        where
+            Self: Sized;
+    }
+}
+
+macro_rules! element {
+    ($($ty:ty),*) => {$(
+        impl Element for $ty {}
+
+        impl private::Sealed for $ty {
+            fn write(self, out: &mut [u8]) {
+                out[..mem::size_of::<Self>()].copy_from_slice(&self.to_ne_bytes());
+            }
+
+            fn from_hex(hex: &str) -> Result<Self, core::num::ParseIntError> {
+                Self::from_str_radix(hex, 16)
+            }
+        }
+    )*};
+}
+
+element!(u8, u16, u32, u64);
+
+/// A pattern of integers with per-element wildcards, searched in a slice of
+/// them, e.g. a table of `u32` ids in memory.
+/// Matches only start at element boundaries.
+///
+/// Elements are compared in native byte order, so the pattern is limited to
+/// [`BYTES`] bytes.
+/// ```
+/// # use patterns::ElementPattern;
+/// let pattern: ElementPattern<u16> = "1234 ? beef".parse().unwrap();
+/// let data = [0x3412, 0x1234, 0, 0xbeef, 0x1234];
+/// assert_eq!(pattern.matches(&data).collect::<Vec<_>>(), [1]);
+/// let pattern = ElementPattern::new(&[Some(1_u32), None, Some(3)]).unwrap();
+/// assert_eq!(pattern.matches(&[0, 1, 2, 3]).collect::<Vec<_>>(), [1]);
+/// ```
+#[derive(Debug)]
+pub struct ElementPattern<T> {
+    pattern: Pattern,
+    element: PhantomData<T>,
+}
+
+impl<T: Element> ElementPattern<T> {
+    /// Creates a pattern from elements, `None` is a wildcard.
+    /// # Errors
+    /// Returns an error if the elements span more than [`BYTES`] bytes or all
+    /// of them are wildcards.
+    #[inline]
+    pub fn new(elements: &[Option<T>]) -> Result<Self, ParsePatternError> {
+        let size = mem::size_of::<T>();
+        if elements.len() * size > BYTES {
+            return Err(ParsePatternError::PatternTooLong);
+        }
+        let mut bytes = [0; BYTES];
+        let mut mask = 0_u64;
+        for (index, element) in elements.iter().enumerate() {
+            if let Some(element) = element {
+                element.write(&mut bytes[index * size..]);
+                let bits = u64::MAX << (u64::BITS as usize - size);
+                mask |= bits >> (index * size);
+            }
+        }
+        let pattern = Pattern::try_from_slice(&bytes[..elements.len() * size], mask)?;
+        Ok(Self {
+            pattern,
+            element: PhantomData,
+        })
+    }
+
+    /// The byte pattern the elements are searched with.
+    #[inline]
+    pub const fn as_pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// Creates an iterator through data that yields the element index of every
+    /// match.
+    #[inline]
+    pub fn matches<'pattern, 'data>(
+        &'pattern self,
+        data: &'data [T],
+    ) -> ElementMatches<'pattern, 'data> {
+        // SAFETY: integers have no padding and every byte is initialized.
+        let bytes =
+            unsafe { slice::from_raw_parts(data.as_ptr().cast::<u8>(), mem::size_of_val(data)) };
+        ElementMatches {
+            scanner: self.pattern.matches_aligned(bytes, mem::size_of::<T>()),
+            size: mem::size_of::<T>(),
+        }
+    }
+}
+
+impl<T> Clone for ElementPattern<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            pattern: self.pattern.clone(),
+            element: PhantomData,
+        }
+    }
+}
+
+impl<T: Element> FromStr for ElementPattern<T> {
+    type Err = ParsePatternError;
+
+    /// Parses whitespace separated hex numbers, wildcards are `?` or `.` like
+    /// for [`Pattern`].
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut elements = [None; BYTES];
+        let mut len = 0;
+        for element in s.split_ascii_whitespace() {
+            let slot = elements
+                .get_mut(len)
+                .ok_or(ParsePatternError::PatternTooLong)?;
+            if !element.starts_with(['?', '.']) {
+                *slot = Some(T::from_hex(element)?);
+            }
+            len += 1;
+        }
+        Self::new(&elements[..len])
+    }
+}
+
+/// An iterator through the element indices where an [`ElementPattern`]
+/// matches, see [`ElementPattern::matches`]
+#[must_use]
+pub struct ElementMatches<'pattern, 'data> {
+    scanner: Aligned<'pattern, 'data>,
+    size: usize,
+}
+
+impl<'pattern, 'data> Iterator for ElementMatches<'pattern, 'data> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.scanner.next().map(|index| index / self.size)
+    }
+}
@@ -0,0 +1,81 @@
+use core::{ops::Range, slice};
+
+use crate::{Pattern, Scanner};
+
+impl Pattern {
+    /// Creates an iterator through data that skips the `excluded` ranges, e.g.
+    /// non-executable sections.
+    /// `excluded` has to be sorted by start. Excluded bytes are never read and
+    /// matches have to lie completely between excluded ranges.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 02");
+    /// let data = [1, 2, 1, 2, 1, 2];
+    /// let found = pattern
+    ///     .matches_excluding(&data, &[1..3])
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(found, [4]);
+    /// ```
+    #[inline]
+    pub fn matches_excluding<'pattern, 'data, 'ranges>(
+        &'pattern self,
+        data: &'data [u8],
+        excluded: &'ranges [Range<usize>],
+    ) -> Excluding<'pattern, 'data, 'ranges> {
+        let mut excluding = Excluding {
+            pattern: self,
+            data,
+            excluded: excluded.iter(),
+            scanner: self.matches(&data[..0]),
+            gap: 0,
+            resume: None,
+        };
+        excluding.open_gap(0);
+        excluding
+    }
+}
+
+/// An iterator through data that skips excluded ranges, see
+/// [`Pattern::matches_excluding`]
+#[must_use]
+pub struct Excluding<'pattern, 'data, 'ranges> {
+    pattern: &'pattern Pattern,
+    data: &'data [u8],
+    excluded: slice::Iter<'ranges, Range<usize>>,
+    scanner: Scanner<'pattern, 'data, 'data>,
+    // Start of the gap the scanner runs through.
+    gap: usize,
+    // Start of the gap after the current one.
+    resume: Option<usize>,
+}
+
+impl<'pattern, 'data, 'ranges> Excluding<'pattern, 'data, 'ranges> {
+    fn open_gap(&mut self, start: usize) {
+        let start = start.min(self.data.len());
+        let (end, resume) = match self.excluded.next() {
+            Some(range) => (
+                range.start.clamp(start, self.data.len()),
+                Some(range.end.max(start)),
+            ),
+            None => (self.data.len(), None),
+        };
+        self.gap = start;
+        self.resume = resume;
+        self.scanner = self.pattern.matches(&self.data[start..end]);
+    }
+}
+
+impl<'pattern, 'data, 'ranges> Iterator for Excluding<'pattern, 'data, 'ranges> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(index) = self.scanner.next() {
+                return Some(self.gap + index);
+            }
+            let resume = self.resume?;
+            self.open_gap(resume);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    load,
+    simd::{Mask, Simd, SimdPartialEq},
+    Pattern, BYTES,
+};
+
+/// How common every byte value is in typical binary data, from 0 for the
+/// rarest to 255 for the most common byte.
+/// Derived from the executables and shared libraries of an x86-64 Linux
+/// system. `00`, `FF` and opcode bytes like `48`, `8B` and `E8` rank highest.
+#[rustfmt::skip]
+const BYTE_RANKS: [u8; 256] = [
+    255, 250, 236, 225, 232, 221, 202, 197, 243, 170, 196, 193, 186, 184, 242, 252, // 00
+    234, 154, 180, 115, 162, 161,  93,  91, 214,  78,  67,  58, 121,  68,  75, 217, // 10
+    245, 124,  94,  53, 248, 181,  32,  64, 205, 165,  55,  83, 110, 127, 173,  89, // 20
+    212, 230, 144,  98, 149, 168, 100,  97, 199, 192,  95,  76, 122, 167,  37,  74, // 30
+    208, 247, 207, 179, 241, 228, 135, 148, 253, 227,  90, 104, 239, 185, 174, 107, // 40
+    200,  52, 140, 203, 201, 178, 113,  96, 134,  51, 109, 147, 160, 171,  85, 229, // 50
+    152, 222, 187, 209, 210, 238, 240, 175, 191, 220,  63, 118, 216, 194, 219, 226, // 60
+    204,  47, 223, 218, 246, 213, 157, 114, 169, 133,  48,  62, 159, 108, 102, 111, // 70
+    211, 117,  44, 235, 224, 233, 119,  56, 136, 251,  21, 249, 116, 237,  72,  65, // 80
+    172,  22,  18,  23,  73,  33,  14,  16,  80,  11,   6,   2,  36,   8,   3,  27, // 90
+    105,  35,  19,  24,  28,   4,  10,  12,  87,   7,  15,  13,  30,   5,   0,  25, // a0
+    103,  20,   1,   9,  43,  17, 131,  60, 142,  66, 126,  31,  69,  39, 141, 106, // b0
+    231, 189, 137, 195, 190, 176, 156, 198, 125, 130,  61,  26,  42,  38,  41,  29, // c0
+    163,  88, 145,  71,  46,  45,  54,  34, 138,  82,  57,  99,  40,  59,  70, 158, // d0
+    164,  84, 112,  49,  79,  50,  86, 101, 244, 215,  92, 166, 139, 120, 129, 183, // e0
+    151,  77, 128, 150,  81, 123, 182, 143, 177, 132, 155, 146, 153, 188, 206, 254, // f0
+];
+
+/// The rank of byte in the built-in table.
+pub(crate) fn built_in_rank(byte: u8) -> u8 {
+    BYTE_RANKS[usize::from(byte)]
+}
+
+/// How often every byte value occurs in the data that is going to be searched.
+/// Patterns anchor their candidate search on their rarest bytes, see
+/// [`Pattern::with_frequencies`]. Without a table, patterns rank bytes by how
+/// common they are in executables, see [`ByteFrequencies::built_in_ranks`].
+/// ```
+/// # use patterns::ByteFrequencies;
+/// let frequencies = ByteFrequencies::from_sample(b"aab");
+/// assert_eq!(frequencies.get(b'a'), 2);
+/// assert_eq!(frequencies.get(b'c'), 0);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ByteFrequencies([u64; 256]);
+
+impl ByteFrequencies {
+    /// Create a table from the number of occurrences of every byte value.
+    #[inline]
+    pub const fn new(counts: [u64; 256]) -> Self {
+        Self(counts)
+    }
+
+    /// Counts the bytes of a sample of the data that is going to be searched.
+    #[inline]
+    pub fn from_sample(sample: &[u8]) -> Self {
+        let mut frequencies = Self([0; 256]);
+        frequencies.add(sample);
+        frequencies
+    }
+
+    /// Adds the bytes of another sample, e.g. to build a table from a corpus
+    /// of several files.
+    /// Chunks that repeat a single byte, like the padding between functions or
+    /// sections, are recognized with one vector comparison and counted at
+    /// once. Other chunks are counted byte by byte.
+    /// ```
+    /// # use patterns::ByteFrequencies;
+    /// let mut frequencies = ByteFrequencies::from_sample(b"aab");
+    /// frequencies.add(b"bc");
+    /// assert_eq!(frequencies.get(b'b'), 2);
+    /// assert_eq!(frequencies.total(), 5);
+    ///
+    /// let mut padded = vec![0xcc; 1000];
+    /// padded[500] = 0xc3;
+    /// frequencies.add(&padded);
+    /// assert_eq!(frequencies.get(0xcc), 999);
+    /// assert_eq!(frequencies.get(0xc3), 1);
+    /// ```
+    #[inline]
+    pub fn add(&mut self, sample: &[u8]) {
+        // Runs of the same byte would make every increment wait for the
+        // previous one, separate tables let them run in parallel.
+        let mut tables = [[0_u64; 256]; 4];
+        let mut count = |bytes: &[u8]| {
+            let chunks = bytes.chunks_exact(4);
+            for &byte in chunks.remainder() {
+                tables[0][usize::from(byte)] += 1;
+            }
+            for chunk in chunks {
+                for (table, &byte) in tables.iter_mut().zip(chunk) {
+                    table[usize::from(byte)] += 1;
+                }
+            }
+        };
+        let chunks = sample.chunks_exact(BYTES);
+        let remainder = chunks.remainder();
+        for chunk in chunks {
+            let first = chunk[0];
+            let equal = load(chunk).simd_eq(Simd::splat(first));
+            if equal.to_bitmask().count_ones() as usize == BYTES {
+                self.0[usize::from(first)] += BYTES as u64;
+            } else {
+                count(chunk);
+            }
+        }
+        count(remainder);
+        for (index, count) in self.0.iter_mut().enumerate() {
+            *count += tables.iter().map(|table| table[index]).sum::<u64>();
+        }
+    }
+
+    /// The ranks of the built-in table that patterns use without a table of
+    /// their own, see [`ByteFrequencies::ranks`]. Derived from the executables
+    /// and shared libraries of an x86-64 Linux system.
+    /// ```
+    /// # use patterns::ByteFrequencies;
+    /// let ranks = ByteFrequencies::built_in_ranks();
+    /// assert_eq!(ranks[0x00], 255);
+    /// assert!(ranks[0xae] < ranks[0xe8]);
+    /// ```
+    #[inline]
+    pub const fn built_in_ranks() -> [u8; 256] {
+        BYTE_RANKS
+    }
+
+    /// The number of occurrences of byte.
+    #[inline]
+    pub const fn get(&self, byte: u8) -> u64 {
+        self.0[byte as usize]
+    }
+
+    /// The number of bytes counted.
+    #[inline]
+    pub fn total(&self) -> u64 {
+        self.0.iter().sum()
+    }
+
+    /// How likely a byte of the counted data is `byte`, 0 for an empty table.
+    #[inline]
+    pub fn probability(&self, byte: u8) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.get(byte) as f64 / total as f64,
+        }
+    }
+
+    /// Ranks every byte value from 0 for the rarest to 255 for the most
+    /// common one. Ties go to the lower byte value.
+    /// ```
+    /// # use patterns::ByteFrequencies;
+    /// let ranks = ByteFrequencies::from_sample(b"aaab").ranks();
+    /// assert_eq!(ranks[usize::from(b'a')], 255);
+    /// assert_eq!(ranks[usize::from(b'b')], 254);
+    /// assert_eq!(ranks[0], 0);
+    /// ```
+    #[inline]
+    pub fn ranks(&self) -> [u8; 256] {
+        let mut order = [0_u8; 256];
+        for (index, byte) in order.iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+        order.sort_unstable_by_key(|&byte| (self.get(byte), byte));
+        let mut ranks = [0; 256];
+        for (rank, byte) in order.into_iter().enumerate() {
+            ranks[usize::from(byte)] = rank as u8;
+        }
+        ranks
+    }
+}
+
+impl Pattern {
+    /// Picks the anchor bytes of the candidate search by how rare they are in
+    /// the given table instead of the built-in one. Useful for data that looks
+    /// nothing like executable code, e.g. text or network captures.
+    /// ```
+    /// # use patterns::{ByteFrequencies, Pattern};
+    /// let sample = b"GET / HTTP/1.1";
+    /// let frequencies = ByteFrequencies::from_sample(sample);
+    /// let pattern = Pattern::new("48 54 54 50").with_frequencies(&frequencies);
+    /// assert_eq!(pattern.matches(sample).collect::<Vec<_>>(), [6]);
+    /// ```
+    #[inline]
+    pub fn with_frequencies(mut self, frequencies: &ByteFrequencies) -> Self {
+        let (first, second) = anchors(&self.bytes, &self.mask, |byte| frequencies.get(byte))
+            .expect("patterns have at least one non-wildcard byte");
+        self.first_offset = first;
+        self.first_byte = Simd::splat(self.bytes[first]);
+        self.second_offset = second;
+        self.second_byte = self.bytes[second];
+        self
+    }
+
+    /// Estimates how often the pattern matches by chance in `len` bytes of
+    /// data whose bytes are distributed like the table, assuming independent
+    /// bytes. Lower is more selective, a good signature stays well below 1 for
+    /// the size of the binary it is meant for.
+    /// ```
+    /// # use patterns::{ByteFrequencies, Pattern};
+    /// let frequencies = ByteFrequencies::new([1; 256]);
+    /// let pattern = Pattern::new("01 ? 03");
+    /// let expected = pattern.expected_matches(&frequencies, (1 << 16) + 2);
+    /// assert!((expected - 1.0).abs() < 1e-3);
+    /// ```
+    #[inline]
+    pub fn expected_matches(&self, frequencies: &ByteFrequencies, len: usize) -> f64 {
+        let positions = (len + 1).saturating_sub(self.len());
+        let mask = self.mask();
+        self.bytes()
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| mask & (1 << (u64::BITS as usize - 1 - index)) != 0)
+            .fold(positions as f64, |expected, (_, &byte)| {
+                expected * frequencies.probability(byte)
+            })
+    }
+}
+
+/// Picks the two rarest non-wildcard bytes of a pattern as the anchors of the
+/// prefilter and returns their offsets, rarest first. Ties go to the lower
+/// offset. If there is only one non-wildcard byte, both offsets point to it.
+/// Returns `None` if all bytes are wildcards.
+pub(crate) fn anchors<R: Ord>(
+    bytes: &Simd<u8, BYTES>,
+    mask: &Mask<i8, BYTES>,
+    frequency: impl Fn(u8) -> R,
+) -> Option<(usize, usize)> {
+    let rank = |offset: usize| frequency(bytes[offset]);
+    let rarest = |mut set: u64| {
+        let mut best: Option<usize> = None;
+        while set != 0 {
+            let offset = set.trailing_zeros() as usize;
+            if best.is_none_or(|best| rank(offset) < rank(best)) {
+                best = Some(offset);
+            }
+            set &= set - 1;
+        }
+        best
+    };
+
+    let set = mask.to_bitmask();
+    let first = rarest(set)?;
+    let second = rarest(set & !(1 << first)).unwrap_or(first);
+    Some((first, second))
+}
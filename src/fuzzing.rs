@@ -0,0 +1,37 @@
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::{Pattern, BYTES};
+
+/// Generates patterns of 1 to [`BYTES`] bytes with random wildcards and at
+/// least one non-wildcard byte. The text form for parser fuzzing is the
+/// [`Display`](core::fmt::Display) output.
+/// ```
+/// # use arbitrary::{Arbitrary, Unstructured};
+/// # use patterns::Pattern;
+/// let mut input = Unstructured::new(&[3, 1, 2, 3, 0b1010_0000, 0, 0, 0, 0, 0, 0, 0]);
+/// let pattern = Pattern::arbitrary(&mut input).unwrap();
+/// assert_eq!(
+///     pattern.to_string().parse::<Pattern>().unwrap().bytes(),
+///     pattern.bytes()
+/// );
+/// ```
+impl<'a> Arbitrary<'a> for Pattern {
+    #[inline]
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.int_in_range(1..=BYTES)?;
+        let mut bytes = [0; BYTES];
+        u.fill_buffer(&mut bytes[..len])?;
+        let covered = !u64::MAX.checked_shr(len as u32).unwrap_or(0);
+        let mut mask = u64::arbitrary(u)? & covered;
+        if mask == 0 {
+            // The last byte keeps the whole length.
+            mask = 1 << (u64::BITS as usize - len);
+        }
+        Ok(Self::from_slice(&bytes[..len], mask))
+    }
+
+    #[inline]
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (1 + 8, Some(1 + BYTES + 8))
+    }
+}
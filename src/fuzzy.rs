@@ -0,0 +1,126 @@
+#[cfg(feature = "alloc")]
+use alloc::{collections::BinaryHeap, vec::Vec};
+
+use crate::{load, simd::SimdPartialEq, Pattern};
+
+/// A position where data is close to a pattern, see [`Pattern::matches_fuzzy`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FuzzyMatch {
+    /// Where the match starts within data
+    pub offset: usize,
+    /// The number of non-wildcard bytes that differ from the pattern
+    pub mismatches: u32,
+}
+
+impl Pattern {
+    /// Creates an iterator through data that yields every position where at
+    /// most `max_mismatches` non-wildcard bytes differ from the pattern, e.g.
+    /// to find a signature again after an update changed a byte of it.
+    /// Compares the whole pattern at every position, so it is a lot slower than
+    /// an exact search.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 02 ? 04");
+    /// let data = [1, 2, 3, 4, 1, 9, 3, 4];
+    /// let found = pattern
+    ///     .matches_fuzzy(&data, 1)
+    ///     .map(|found| (found.offset, found.mismatches))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(found, [(0, 0), (4, 1)]);
+    /// ```
+    #[inline]
+    pub fn matches_fuzzy<'pattern, 'data>(
+        &'pattern self,
+        data: &'data [u8],
+        max_mismatches: u32,
+    ) -> Fuzzy<'pattern, 'data> {
+        Fuzzy {
+            pattern: self,
+            data,
+            max_mismatches,
+            next: 0,
+        }
+    }
+
+    /// Finds the `top_k` positions in data that are closest to the pattern,
+    /// i.e. where the fewest non-wildcard bytes differ.
+    /// Sorted by the number of mismatches and then by offset, ties go to the
+    /// earlier position.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 02 03 04");
+    /// let data = [1, 2, 0, 4, 0, 0, 1, 2, 3, 9];
+    /// let best = pattern.best_matches(&data, 2);
+    /// let best = best.iter().map(|found| (found.offset, found.mismatches));
+    /// assert_eq!(best.collect::<Vec<_>>(), [(0, 1), (6, 1)]);
+    ///
+    /// // Every position, closest first.
+    /// let all = pattern.best_matches(&data, usize::MAX);
+    /// assert_eq!(all.len(), 7);
+    /// assert_eq!(all[0].offset, 0);
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn best_matches(&self, data: &[u8], top_k: usize) -> Vec<FuzzyMatch> {
+        let end = (data.len() + 1).saturating_sub(self.len());
+        // There are no more positions than that.
+        let top_k = top_k.min(end);
+        if top_k == 0 {
+            return Vec::new();
+        }
+        // The worst of the best positions so far is on top.
+        let mut best = BinaryHeap::with_capacity(top_k + 1);
+        for offset in 0..end {
+            let mismatches = self.mismatches(data, offset);
+            if best.len() == top_k {
+                match best.peek() {
+                    Some(&(worst, _)) if mismatches < worst => {
+                        best.pop();
+                    }
+                    _ => continue,
+                }
+            }
+            best.push((mismatches, offset));
+        }
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|(mismatches, offset)| FuzzyMatch { offset, mismatches })
+            .collect()
+    }
+
+    /// The number of non-wildcard bytes of the pattern that differ from data
+    /// at index. Data past its end counts as 0.
+    #[inline]
+    pub(crate) fn mismatches(&self, data: &[u8], index: usize) -> u32 {
+        let equal = load(&data[index..]).simd_eq(self.bytes).to_bitmask();
+        (!equal & self.mask.to_bitmask()).count_ones()
+    }
+}
+
+/// An iterator through the positions close to a pattern, see
+/// [`Pattern::matches_fuzzy`]
+#[must_use]
+pub struct Fuzzy<'pattern, 'data> {
+    pattern: &'pattern Pattern,
+    data: &'data [u8],
+    max_mismatches: u32,
+    next: usize,
+}
+
+impl<'pattern, 'data> Iterator for Fuzzy<'pattern, 'data> {
+    type Item = FuzzyMatch;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = (self.data.len() + 1).checked_sub(self.pattern.len())?;
+        while self.next < end {
+            let offset = self.next;
+            self.next += 1;
+            let mismatches = self.pattern.mismatches(self.data, offset);
+            if mismatches <= self.max_mismatches {
+                return Some(FuzzyMatch { offset, mismatches });
+            }
+        }
+        None
+    }
+}
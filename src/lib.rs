@@ -1,6 +1,11 @@
 //! # Pattern matching library
 //! Allows you to search for a pattern within data via an iterator interface.
 //! This library uses the core::simd abstraction and does not allocate.
+//! The `stable` feature builds on stable Rust without core::simd, at about
+//! half the throughput.
+//! Under Miri, only the portable code paths are used, without intrinsics or
+//! runtime CPU detection, so that crates depending on this one can run their
+//! tests in Miri.
 //!
 //! ## Usage
 //! ```
@@ -17,28 +22,207 @@
 //! }
 //! ```
 
-#![feature(portable_simd)]
+#![cfg_attr(
+    not(feature = "stable"),
+    feature(portable_simd, strict_provenance_lints, try_trait_v2),
+    deny(fuzzy_provenance_casts, lossy_provenance_casts)
+)]
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(not(feature = "stable"))]
+use core::ops::Try;
 use core::{
+    convert::Infallible,
+    error::Error,
+    fmt,
+    marker::PhantomData,
     num::ParseIntError,
-    ops::{BitAnd, Deref, Not},
-    simd::{cmp::SimdPartialEq, Mask, Simd},
+    ops::{BitAnd, ControlFlow, Deref, Not},
     str::FromStr,
 };
 
+#[cfg(feature = "std")]
+pub use self::cache::ScanCache;
+#[cfg(feature = "std")]
+pub use self::database::{DatabaseErrorReason, ParseDatabaseError, PatternDatabase};
+#[cfg(feature = "alloc")]
+pub use self::dynlen::{DynLenMatches, DynLenPattern};
+#[cfg(all(
+    feature = "std",
+    any(target_os = "linux", target_os = "android", windows)
+))]
+pub use self::process::{ModuleInfo, ModuleMatches, OwnProcessMatches, Region};
+#[cfg(all(
+    feature = "std",
+    any(
+        target_os = "linux",
+        target_os = "android",
+        all(windows, feature = "windows")
+    )
+))]
+pub use self::process::{Process, ProcessMatches};
+#[cfg(feature = "std")]
+pub use self::read::{BufReaderMatches, ReaderMatches};
+#[cfg(feature = "object")]
+pub use self::sections::{SectionMatch, SectionMatches};
+#[cfg(feature = "std")]
+pub use self::signature_cache::{ParseSignatureCacheError, SignatureCache};
+pub use self::{
+    adjust::{OffsetMatches, OffsetPattern},
+    aligned::Aligned,
+    backref::{BackRefMatches, BackRefPattern},
+    bits::{BitMatches, BitPattern},
+    chain::{Chain, ChainScanner, Proximity, ProximityScanner},
+    compiled::{CompiledPattern, CompiledPatternError},
+    dispatch::Dispatch,
+    element::{Element, ElementMatches, ElementPattern},
+    exclude::Excluding,
+    frequency::ByteFrequencies,
+    fuzzy::{Fuzzy, FuzzyMatch},
+    multi::{MultiScanner, PatternSet, SetScanner},
+    patch::{Edit, Edits, Replacement},
+    pointer::{DataSource, OutOfBounds, PointerChain},
+    reverse::ReverseScanner,
+    rules::{ParseRuleError, Rule},
+    signature::DiffError,
+    span::{Match, Merged, Spans},
+    split::SplitPoints,
+    stream::{SegmentMatches, StreamMatches, StreamScanner},
+    unique::{Location, Occurrences, Uniqueness},
+    value_scan::{Value, ValueMatches, ValueScan},
+    xref::{Xref, XrefKind, Xrefs},
+};
+use self::{
+    offsets::Offsets,
+    simd::{Mask, Simd, SimdPartialEq},
+};
+
+mod adjust;
+mod aligned;
+mod backref;
+mod bits;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+mod calibrate;
+mod chain;
+mod compiled;
+#[cfg(feature = "std")]
+mod database;
+mod dispatch;
+#[cfg(feature = "alloc")]
+mod dynlen;
+mod element;
+mod exclude;
+mod frequency;
+#[cfg(feature = "arbitrary")]
+mod fuzzing;
+mod fuzzy;
+mod multi;
+mod offsets;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod patch;
+mod pointer;
+#[cfg(all(
+    feature = "std",
+    any(target_os = "linux", target_os = "android", windows)
+))]
+mod process;
+#[cfg(feature = "std")]
+mod read;
+#[cfg(feature = "reference")]
+pub mod reference;
+mod reverse;
+mod rules;
+#[cfg(feature = "object")]
+mod sections;
+mod signature;
+#[cfg(feature = "std")]
+mod signature_cache;
+mod simd;
+mod span;
+mod split;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+mod stream;
+mod swar;
+mod unique;
+mod value_scan;
+mod xref;
+
+// Sharing patterns and moving scanners across threads is part of the API.
+const _: () = {
+    const fn send_sync<T: Send + Sync>() {}
+    send_sync::<Pattern>();
+    send_sync::<Scanner<'_, '_, '_>>();
+    send_sync::<Limited<'_, '_, '_>>();
+    send_sync::<MultiScanner<'_, '_>>();
+    send_sync::<PatternSet<'_, usize>>();
+    send_sync::<SetScanner<'_, '_, usize>>();
+    send_sync::<StreamScanner<'_>>();
+    send_sync::<StreamMatches<'_, '_>>();
+    send_sync::<ReverseScanner<'_, '_>>();
+    send_sync::<ChainScanner<'_, '_>>();
+    send_sync::<ProximityScanner<'_, '_>>();
+    send_sync::<Excluding<'_, '_, '_>>();
+    send_sync::<Aligned<'_, '_>>();
+    send_sync::<Spans<'_, '_, '_>>();
+    send_sync::<Merged<'_, '_, '_>>();
+    send_sync::<ByteFrequencies>();
+    send_sync::<Replacement>();
+    send_sync::<Fuzzy<'_, '_>>();
+    send_sync::<Rule<'_>>();
+    send_sync::<Occurrences<'_, '_>>();
+    send_sync::<BitPattern>();
+    send_sync::<BitMatches<'_, '_>>();
+    send_sync::<ElementPattern<u32>>();
+    send_sync::<ElementMatches<'_, '_>>();
+    send_sync::<ValueScan<f32>>();
+    send_sync::<ValueMatches<'_, '_, f32>>();
+    send_sync::<Edits<'_, '_, '_>>();
+    send_sync::<Xrefs<'_>>();
+};
+
 /// Determines the LANES size. i.e.: register size;
 /// Every block of data is processed in chunks of `BYTES` bytes.
+///
+/// It is the same on every target, and so is the maximum pattern length.
+/// Narrower vector registers process a chunk in several steps, the
+/// instructions for that are picked per CPU, see [`Dispatch`].
 pub const BYTES: usize = 64;
 
 /// An iterator for searching a given pattern in data
+///
+/// Scanners are [`Send`] and [`Sync`], they can be moved to other threads to
+/// continue the search there.
 #[must_use]
 pub struct Scanner<'pattern, 'data: 'cursor, 'cursor> {
     pattern: &'pattern Pattern,
     data: &'data [u8],
-    cursor: &'cursor [u8],
+    // The index of the next position to search within the haystack, which is
+    // data or, for the last few bytes, the buffer.
+    cursor: usize,
+    // The offset of the haystack within data.
     position: usize,
+    // The index within the haystack behind the last match that was yielded or
+    // skipped. The cursor is never behind it once a search starts.
+    resume: usize,
+    // Verified matches between resume and the cursor that have not been
+    // yielded yet, as bits relative to resume.
+    pending: u64,
+    end: usize,
+    overlapping: bool,
+    streaming: bool,
+    adaptive: Adaptive,
+    phase: Phase,
     buffer: Buffer,
+    _cursor: PhantomData<&'cursor [u8]>,
 }
 
 impl<'pattern, 'data: 'cursor, 'cursor> Scanner<'pattern, 'data, 'cursor> {
@@ -48,10 +232,163 @@ impl<'pattern, 'data: 'cursor, 'cursor> Scanner<'pattern, 'data, 'cursor> {
         Scanner {
             pattern,
             data,
-            cursor: data,
+            cursor: 0,
             buffer: Buffer::new(),
             position: 0,
+            resume: 0,
+            pending: 0,
+            end: data.len(),
+            overlapping: true,
+            streaming: false,
+            adaptive: Adaptive::new(pattern),
+            phase: Phase::Data,
+            _cursor: PhantomData,
+        }
+    }
+
+    /// Only yield matches that don't overlap with the previous match.
+    /// After a match, scanning continues behind its last byte.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 01");
+    /// let data = [1, 1, 1, 1, 1];
+    /// assert_eq!(pattern.matches(&data).count(), 4);
+    /// assert_eq!(pattern.matches(&data).non_overlapping().count(), 2);
+    /// ```
+    #[inline]
+    pub fn non_overlapping(mut self) -> Self {
+        self.overlapping = false;
+        self
+    }
+
+    /// Hints the CPU to not keep the scanned data in its caches.
+    /// Meant for one-shot scans over gigabytes of data that would otherwise
+    /// evict the working set of the rest of the program. Only has an effect on
+    /// x86-64, and makes smaller scans slower.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01");
+    /// let data = [0, 1, 0, 1];
+    /// assert_eq!(pattern.matches(&data).streaming().count(), 2);
+    /// ```
+    #[inline]
+    pub fn streaming(mut self) -> Self {
+        self.streaming = true;
+        self
+    }
+
+    /// Continues scanning at `offset` within data, skipping all matches that
+    /// start before it. The scanner never moves backwards, offsets before the
+    /// current position are ignored.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01");
+    /// let mut scanner = pattern.matches(&[1, 1, 0, 1, 1]);
+    /// assert_eq!(scanner.next(), Some(0));
+    /// scanner.skip_to(3);
+    /// assert_eq!(scanner.next(), Some(3));
+    /// ```
+    #[inline]
+    pub fn skip_to(&mut self, offset: usize) {
+        let current = self.offset();
+        if offset <= current {
+            return;
         }
+        let skip = offset - current;
+        self.pending = shift_out(self.pending, skip);
+        self.resume += skip;
+    }
+
+    /// The offset within data the next search starts at.
+    /// Every match before this offset has been yielded already.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01");
+    /// let mut scanner = pattern.matches(&[0, 1, 0, 0]);
+    /// assert_eq!(scanner.next(), Some(1));
+    /// assert_eq!(scanner.offset(), 2);
+    /// assert_eq!(scanner.remaining(), 2);
+    /// ```
+    #[inline]
+    pub fn offset(&self) -> usize {
+        if self.phase == Phase::Done {
+            return self.end;
+        }
+        // The buffer is padded past the end of data.
+        (self.position + self.resume).min(self.end)
+    }
+
+    /// The number of bytes of data that have not been searched yet.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.end - self.offset()
+    }
+
+    /// Yields pointers to the matches instead of offsets, e.g. to hand them
+    /// to C code.
+    /// The pointers are derived from data and carry its provenance, reading
+    /// the length of the pattern from them is sound as long as data is.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let data = [0, 1, 2, 1];
+    /// let pattern = Pattern::new("01");
+    /// let found = pattern.matches(&data).as_ptrs().collect::<Vec<_>>();
+    /// assert_eq!(found, [&data[1] as *const u8, &data[3]]);
+    /// // Safety: the pointers point into data, which is still borrowed.
+    /// assert_eq!(unsafe { *found[1] }, 1);
+    /// ```
+    #[inline]
+    pub fn as_ptrs(self) -> Pointers<'pattern, 'data, 'cursor> {
+        Pointers { scanner: self }
+    }
+
+    /// Only yields the matches `filter` accepts. It gets the offset of every
+    /// match and data from there to its end, e.g. to check a checksum behind
+    /// the pattern or decode a field.
+    /// The filter runs during the scan, rejected matches still count as
+    /// matches for [`Scanner::non_overlapping`].
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("ff ?");
+    /// let data = [0xff, 1, 0xff, 2, 0xff, 3];
+    /// let even = pattern
+    ///     .matches(&data)
+    ///     .filter_matches(|_, found| found[1] % 2 == 0)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(even, [2]);
+    /// ```
+    #[inline]
+    pub fn filter_matches<F>(self, filter: F) -> Filtered<'pattern, 'data, 'cursor, F>
+    where
+        F: FnMut(usize, &[u8]) -> bool,
+    {
+        Filtered {
+            scanner: self,
+            filter,
+        }
+    }
+
+    /// Writes the next matches into `results` without allocating.
+    /// Returns how many offsets were written and whether `results` filled up
+    /// before the scanner ran out of data. In that case more matches may
+    /// follow and another call continues where this one stopped.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01");
+    /// let mut scanner = pattern.matches(&[1, 1, 1]);
+    /// let mut results = [0; 2];
+    /// assert_eq!(scanner.collect_into_slice(&mut results), (2, true));
+    /// assert_eq!(scanner.collect_into_slice(&mut results), (1, false));
+    /// ```
+    #[inline]
+    pub fn collect_into_slice(&mut self, results: &mut [usize]) -> (usize, bool) {
+        for (found, result) in results.iter_mut().enumerate() {
+            let Some(index) = self.next() else {
+                return (found, false);
+            };
+            *result = index;
+        }
+        (results.len(), true)
     }
 }
 
@@ -60,96 +397,456 @@ impl<'pattern, 'data: 'cursor, 'cursor> Iterator for Scanner<'pattern, 'data, 'c
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        if self.pending == 0 && !self.refill() {
+            return None;
+        }
+        let skip = self.pending.trailing_zeros() as usize;
+        let index = self.resume + skip;
+        // Without overlaps, the next match can only start behind this one.
+        let step = if self.overlapping {
+            1
+        } else {
+            self.pattern.len()
+        };
+        self.pending = shift_out(self.pending, skip + step);
+        self.resume = index + step;
+
+        let index = self.position + index;
+        // The buffer is padded with zeros past the end of data, don't report
+        // matches that would need those.
+        if index + self.pattern.len() > self.end {
+            self.finish();
+            return None;
+        }
+        Some(index)
+    }
+
+    #[inline]
+    fn last(self) -> Option<Self::Item> {
+        // Without overlaps the last match depends on all previous ones.
+        if self.phase != Phase::Data || !self.overlapping {
+            // Only the short tail is left, walking it is cheap.
+            return self.fold(None, |_, index| Some(index));
+        }
+        let start = self.unsearched();
+        self.pattern
+            .rfind(&self.data[start..])
+            .map(|index| start + index)
+            .or_else(|| Some(self.resume + self.pending.checked_ilog2()? as usize))
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        // Only the scanner itself prefetches for streaming.
+        if self.phase != Phase::Data || !self.overlapping || self.streaming {
+            return self.fold(0, |count, _| count + 1);
+        }
+        self.pending.count_ones() as usize + self.pattern.count(&self.data[self.unsearched()..])
+    }
+
+    #[inline]
+    fn fold<B, F>(mut self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, Self::Item) -> B,
+    {
+        match self.fold_while(init, |acc, index| {
+            ControlFlow::<Infallible, B>::Continue(f(acc, index))
+        }) {
+            ControlFlow::Continue(acc) => acc,
+            ControlFlow::Break(never) => match never {},
+        }
+    }
+
+    #[cfg(not(feature = "stable"))]
+    #[inline]
+    fn try_fold<B, F, R>(&mut self, init: B, mut f: F) -> R
+    where
+        F: FnMut(B, Self::Item) -> R,
+        R: Try<Output = B>,
+    {
+        match self.fold_while(init, |acc, index| f(acc, index).branch()) {
+            ControlFlow::Continue(acc) => R::from_output(acc),
+            ControlFlow::Break(residual) => R::from_residual(residual),
+        }
+    }
+}
+
+impl<'pattern, 'data: 'cursor, 'cursor> Scanner<'pattern, 'data, 'cursor> {
+    /// The slice the cursor points into.
+    #[inline]
+    fn haystack(&self) -> &[u8] {
+        match self.phase {
+            Phase::Data => self.data,
+            Phase::Tail | Phase::Done => &self.buffer,
+        }
+    }
+
+    /// The index the next search starts at, within the haystack.
+    #[inline]
+    fn unsearched(&self) -> usize {
+        self.cursor.max(self.resume).min(self.haystack().len())
+    }
+
+    /// Searches for the next batch of matches. Returns `false` if there are
+    /// none left.
+    #[inline]
+    fn refill(&mut self) -> bool {
         loop {
-            if let Some(index) = find_in_buffer(self.pattern, self.data, &mut self.cursor) {
-                return Some(self.position + index);
+            self.cursor = self.unsearched();
+            let haystack = match self.phase {
+                Phase::Data => self.data,
+                Phase::Tail => &self.buffer,
+                Phase::Done => return false,
+            };
+            let found = dispatch::fold_chunks(
+                self.pattern,
+                haystack,
+                &mut self.cursor,
+                self.streaming,
+                &mut self.adaptive,
+                (),
+                |(), base, matches| ControlFlow::Break((base, matches)),
+            );
+            if let ControlFlow::Break((base, matches)) = found {
+                self.resume = base;
+                self.pending = matches;
+                return true;
             }
-            // `find_in_buffer` can only check `BYTES` amount of bytes at once, no less.
-            // It returns `None` if it ran out of space in data to look for matches.
+            // `fold_chunks` can only check `BYTES` amount of bytes at once, no less.
+            // It stops without a batch if it ran out of space in data to look for matches.
             // For the final bit, copy the remaining data to a buffer and search there
-            // again, but only do that once, otherwise we get an infinite loop.
-            // Also remember that this is an iterator. This function gets called multiple
-            // times and in every possible state of `self`.
-            if self.buffer.in_use() {
-                return None;
+            // again. The buffer is padded, so that search runs to its end.
+            match self.phase {
+                Phase::Data => self.copy_to_buffer(),
+                Phase::Tail | Phase::Done => {
+                    self.finish();
+                    return false;
+                }
             }
-            self.copy_to_buffer();
         }
     }
-}
 
-impl<'pattern, 'data: 'cursor, 'cursor> Scanner<'pattern, 'data, 'cursor> {
+    /// Continues the search in the buffer, from the cursor to the end of data.
     fn copy_to_buffer(&mut self) {
-        self.save_position();
-        self.buffer.copy_from(self.cursor);
-        // Safety:
-        // This is instant UB, but I don't know how to fix this.
-        // This is UB because we violate aliasing and extend the lifetime.
-        // self.buffer is a mutable reference while self.data is an immutable reference.
-        unsafe {
-            self.cursor = &*(&*self.buffer as *const [u8]);
-            self.data = &*(&*self.buffer as *const [u8]);
+        self.buffer.copy_from(&self.data[self.cursor..]);
+        self.phase = Phase::Tail;
+        self.position = self.cursor;
+        self.resume = self.resume.saturating_sub(self.cursor);
+        self.cursor = 0;
+    }
+
+    /// Feeds all matches to f until it breaks.
+    /// Data is searched in a tight loop without the checks `next` needs for
+    /// the buffer, which only holds the last few bytes.
+    #[inline]
+    fn fold_while<B, T>(
+        &mut self,
+        init: B,
+        mut f: impl FnMut(B, usize) -> ControlFlow<T, B>,
+    ) -> ControlFlow<T, B> {
+        let mut acc = init;
+        while self.pending != 0 {
+            let Some(index) = self.next() else {
+                return ControlFlow::Continue(acc);
+            };
+            acc = f(acc, index)?;
         }
+        // Every match found in data ends within data.
+        if self.overlapping && self.phase == Phase::Data {
+            self.cursor = self.unsearched();
+            let result = dispatch::fold_chunks(
+                self.pattern,
+                self.data,
+                &mut self.cursor,
+                self.streaming,
+                &mut self.adaptive,
+                acc,
+                |mut acc, base, mut matches| {
+                    while matches != 0 {
+                        let offset = matches.trailing_zeros() as usize;
+                        acc = match f(acc, base + offset) {
+                            ControlFlow::Continue(acc) => acc,
+                            ControlFlow::Break(result) => {
+                                // Keep the rest of the chunk for later.
+                                self.resume = base + offset + 1;
+                                self.pending = shift_out(matches, offset + 1);
+                                return ControlFlow::Break(result);
+                            }
+                        };
+                        matches &= matches - 1;
+                    }
+                    ControlFlow::Continue(acc)
+                },
+            );
+            acc = match result {
+                ControlFlow::Continue(acc) => acc,
+                ControlFlow::Break(result) => return ControlFlow::Break(result),
+            };
+            self.resume = self.cursor;
+            self.copy_to_buffer();
+        }
+        for index in self {
+            acc = f(acc, index)?;
+        }
+        ControlFlow::Continue(acc)
     }
 
-    fn save_position(&mut self) {
-        // Safety: This is fine because we make sure that cursor always points to data
-        unsafe {
-            self.position = self.cursor.as_ptr().offset_from(self.data.as_ptr()) as usize;
+    /// Ends the search, dropping all matches that were not yielded yet.
+    #[inline]
+    fn finish(&mut self) {
+        self.phase = Phase::Done;
+        self.pending = 0;
+    }
+}
+
+/// The part of data a [`Scanner`] searches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    /// Data itself, as long as whole chunks are left.
+    Data,
+    /// The copy of the last few bytes of data in the buffer.
+    Tail,
+    /// Nothing, all matches were yielded.
+    Done,
+}
+
+/// An iterator that stops after a maximum number of matches, see
+/// [`Pattern::matches_limited`]
+#[must_use]
+pub struct Limited<'pattern, 'data: 'cursor, 'cursor> {
+    scanner: Scanner<'pattern, 'data, 'cursor>,
+    remaining: usize,
+    exhausted: bool,
+}
+
+impl<'pattern, 'data: 'cursor, 'cursor> Limited<'pattern, 'data, 'cursor> {
+    /// Returns `true` if the iterator stopped because it hit the limit, i.e.
+    /// more matches may follow in data that was not scanned yet.
+    #[inline]
+    pub const fn limit_reached(&self) -> bool {
+        self.remaining == 0 && !self.exhausted
+    }
+
+    /// Returns the underlying scanner to continue scanning past the limit.
+    #[inline]
+    pub fn into_inner(self) -> Scanner<'pattern, 'data, 'cursor> {
+        self.scanner
+    }
+}
+
+impl<'pattern, 'data: 'cursor, 'cursor> Iterator for Limited<'pattern, 'data, 'cursor> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let index = self.scanner.next();
+        match index {
+            Some(_) => self.remaining -= 1,
+            None => self.exhausted = true,
         }
+        index
+    }
+}
+
+/// An iterator yielding pointers to the matches, see [`Scanner::as_ptrs`]
+#[must_use]
+pub struct Pointers<'pattern, 'data: 'cursor, 'cursor> {
+    scanner: Scanner<'pattern, 'data, 'cursor>,
+}
+
+impl<'pattern, 'data: 'cursor, 'cursor> Iterator for Pointers<'pattern, 'data, 'cursor> {
+    type Item = *const u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.scanner.next()?;
+        Some(self.scanner.data[index..].as_ptr())
     }
 }
 
-fn find_in_buffer(pattern: &Pattern, data: &[u8], cursor: &mut &[u8]) -> Option<usize> {
+/// An iterator yielding the matches a filter accepts, see
+/// [`Scanner::filter_matches`]
+#[must_use]
+pub struct Filtered<'pattern, 'data: 'cursor, 'cursor, F> {
+    scanner: Scanner<'pattern, 'data, 'cursor>,
+    filter: F,
+}
+
+impl<'pattern, 'data: 'cursor, 'cursor, F> Iterator for Filtered<'pattern, 'data, 'cursor, F>
+where
+    F: FnMut(usize, &[u8]) -> bool,
+{
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.scanner.data;
+        self.scanner
+            .find(|&index| (self.filter)(index, &data[index..]))
+    }
+}
+
+/// Feeds the matches in haystack from the cursor on to f, one chunk at a
+/// time, until f breaks or there is no more space to search in.
+/// Every candidate of a chunk is verified before f is called with the start
+/// of the chunk and the matches in it as bits relative to that. The cursor is
+/// already behind the chunk by then.
+#[inline(always)]
+fn fold_chunks<B, T>(
+    pattern: &Pattern,
+    haystack: &[u8],
+    cursor: &mut usize,
+    streaming: bool,
+    adaptive: &mut Adaptive,
+    init: B,
+    mut f: impl FnMut(B, usize, u64) -> ControlFlow<T, B>,
+) -> ControlFlow<T, B> {
+    let mut acc = init;
     loop {
-        if cursor.len() < BYTES + pattern.wildcard_prefix {
-            break None;
+        if haystack.len() - *cursor < BYTES + pattern.reach(adaptive.prefilter) {
+            break ControlFlow::Continue(acc);
+        }
+        let window = &haystack[*cursor..];
+        if streaming {
+            prefetch_non_temporal(window.as_ptr().wrapping_add(PREFETCH_DISTANCE));
         }
 
-        // We can skip bytes that are wildcards.
-        let search = Simd::from_slice(&cursor[pattern.wildcard_prefix..]);
-        // Look for the first non wildcard byte.
-        let first_byte = search.simd_eq(pattern.first_byte).to_bitmask();
-
-        // If no match was found, shift by the amount of bytes we check at once and
-        // start over.
-        if first_byte == 0 {
-            *cursor = &cursor[BYTES..];
-            continue;
+        // Skip ahead to the anchor byte of the pattern.
+        let search = Simd::from_slice(&window[pattern.first_offset..]);
+        // Look for the anchor byte.
+        let mut candidates = search.simd_eq(pattern.first_byte).to_bitmask();
+        if adaptive.prefilter == Prefilter::TwoBytes {
+            let search: Simd<u8, BYTES> = Simd::from_slice(&window[pattern.second_offset..]);
+            candidates &= search
+                .simd_eq(Simd::splat(pattern.second_byte))
+                .to_bitmask();
         }
-        // ... else shift the cursor to match the first match.
-        *cursor = &cursor[first_byte.trailing_zeros() as usize..];
+        let all_candidates = candidates;
 
-        if cursor.len() < BYTES {
-            break None;
+        // A pattern with a single non-wildcard byte matches wherever its anchor
+        // does, there is nothing left to verify.
+        let mut matches = candidates;
+        let mut advance = BYTES;
+        if !pattern.is_single_byte() {
+            matches = 0;
+            // Check every candidate of this chunk without reloading the anchors.
+            while candidates != 0 {
+                let offset = candidates.trailing_zeros() as usize;
+                if window.len() < offset + BYTES {
+                    // Leave the rest to the caller, starting at this candidate.
+                    advance = offset;
+                    break;
+                }
+
+                let search = Simd::from_slice(&window[offset..]);
+                // Check `BYTES` amount of bytes at the same time.
+                let result = search.simd_eq(pattern.bytes);
+                // Filter out results we are not interested in.
+                let filtered_result = result.bitand(pattern.mask);
+                // Perform an equality check on all registers of the final result.
+                // Essentially this boils down to `result & mask == mask`
+                if filtered_result == pattern.mask {
+                    matches |= 1 << offset;
+                }
+                // Clear the lowest set bit.
+                candidates &= candidates - 1;
+            }
         }
 
-        let search = Simd::from_slice(cursor);
-        // Check `BYTES` amount of bytes at the same time.
-        let result = search.simd_eq(pattern.bytes);
-        // Filter out results we are not interested in.
-        let filtered_result = result.bitand(pattern.mask);
-        // Save the position within data.
-        // Safety: This is fine because we make sure that cursor always points to data
-        let index = unsafe { cursor.as_ptr().offset_from(data.as_ptr()) };
-        // Shift the cursor by one to not check the same data again.
-        *cursor = &cursor[1..];
-        // Perform an equality check on all registers of the final result.
-        // Essentially this boils down to `result & mask == mask`
-        if filtered_result == pattern.mask {
-            return Some(index as usize);
+        if advance == BYTES {
+            adaptive.record(all_candidates, matches);
+        }
+        let base = *cursor;
+        // Shift by the amount of bytes we checked and start over.
+        *cursor += advance;
+        if matches != 0 {
+            acc = f(acc, base, matches)?;
+        }
+        if advance < BYTES {
+            break ControlFlow::Continue(acc);
         }
     }
 }
 
+/// How far ahead of the cursor streaming scanners prefetch.
+const PREFETCH_DISTANCE: usize = 8 * BYTES;
+
+/// Hints the CPU to load the cache line at `address` without keeping it in the
+/// outer caches.
+#[inline(always)]
+fn prefetch_non_temporal(address: *const u8) {
+    #[cfg(all(target_arch = "x86_64", not(miri)))]
+    // Safety: Prefetches are only hints and never fault, not even on invalid
+    // addresses.
+    unsafe {
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_NTA};
+        _mm_prefetch::<_MM_HINT_NTA>(address.cast());
+    }
+    #[cfg(any(not(target_arch = "x86_64"), miri))]
+    let _ = address;
+}
+
 /// A prepared pattern
+///
+/// Patterns are [`Send`] and [`Sync`], one pattern can be shared by all
+/// threads, e.g. in a `static`:
+/// ```
+/// # use patterns::Pattern;
+/// use std::sync::LazyLock;
+///
+/// static PATTERN: LazyLock<Pattern> = LazyLock::new(|| Pattern::new("01 ? 03"));
+/// let found = std::thread::spawn(|| PATTERN.find(&[0, 1, 2, 3])).join();
+/// assert_eq!(found.unwrap(), Some(1));
+/// ```
 #[must_use]
 #[derive(Clone, Debug)]
 pub struct Pattern {
     pub(crate) bytes: Simd<u8, BYTES>,
     pub(crate) mask: Mask<i8, BYTES>,
-    pub(crate) wildcard_prefix: usize,
+    // The rarest non-wildcard byte, the anchor of every candidate.
+    pub(crate) first_offset: usize,
     pub(crate) first_byte: Simd<u8, BYTES>,
+    // The second rarest non-wildcard byte, or the first one again if there is
+    // none.
+    pub(crate) second_offset: usize,
+    pub(crate) second_byte: u8,
+    pub(crate) prefilter: Prefilter,
+}
+
+/// How candidate positions are found before the whole pattern is checked.
+/// Patterns without wildcards start out with [`Prefilter::TwoBytes`], all
+/// others with [`Prefilter::FirstByte`].
+/// A scan switches from [`Prefilter::FirstByte`] to [`Prefilter::TwoBytes`]
+/// on its own once most candidates turn out false, e.g. for a `00` anchor in
+/// zero-filled data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Prefilter {
+    /// Compare the rarest non-wildcard byte.
+    FirstByte,
+    /// Compare the two rarest non-wildcard bytes.
+    /// Produces far fewer false candidates in data where even the rarest byte
+    /// is common, at the cost of a second comparison per chunk.
+    TwoBytes,
+}
+
+impl Prefilter {
+    /// The prefilter a pattern with this mask starts out with.
+    /// Literal needles are common and the second comparison is nearly free
+    /// next to the verification it saves, like in two-anchor `memmem`
+    /// algorithms.
+    fn for_mask(mask: &Mask<i8, BYTES>) -> Self {
+        let mask = mask.to_bitmask();
+        // Without wildcards, the mask is a single run of bits starting at 0.
+        if mask & mask.wrapping_add(1) == 0 {
+            Self::TwoBytes
+        } else {
+            Self::FirstByte
+        }
+    }
 }
 
 impl Pattern {
@@ -163,28 +860,127 @@ impl Pattern {
     }
 
     /// Create a pattern from a byte slice and a mask.
-    /// Byte slices longer than [`BYTES`] are cut short.
+    /// Byte slices longer than [`BYTES`] are cut short, use
+    /// [`Pattern::try_from_slice`] to get an error instead.
     /// Mask expects a [`u64`] bitencoding. A 0 bit marks the byte as wildcard.
     /// Mask is trimmed to `bytes.len()`.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::from_slice(&[1; 80], u64::MAX);
+    /// assert_eq!(pattern.matches(&[1; 64]).count(), 1);
+    /// ```
     /// # Panics
     /// Panics when all bytes are masked as wildcards.
     #[inline]
     pub fn from_slice(bytes: &[u8], mask: u64) -> Self {
-        let mut input: [u8; BYTES] = [0; BYTES];
         let len = bytes.len().min(BYTES);
-        input[..len].copy_from_slice(bytes);
-        let mask = u64::MAX.checked_shr(len as u32).unwrap_or(0).not() & mask;
-        let bytes = Simd::from_array(input);
-        let mask = Mask::from_bitmask(mask.reverse_bits());
+        Self::try_from_slice(&bytes[..len], mask).unwrap()
+    }
 
-        let (wildcard_prefix, first_byte) = get_first_byte(&bytes, &mask, len).unwrap();
+    /// Create a pattern from a byte slice and a mask, see
+    /// [`Pattern::from_slice`].
+    /// ```
+    /// # use patterns::{ParsePatternError, Pattern};
+    /// assert!(Pattern::try_from_slice(&[1, 2], 0b11 << 62).is_ok());
+    /// assert!(matches!(
+    ///     Pattern::try_from_slice(&[1, 2], 0),
+    ///     Err(ParsePatternError::MissingNonWildcardByte)
+    /// ));
+    /// assert!(matches!(
+    ///     Pattern::try_from_slice(&[1; 65], u64::MAX),
+    ///     Err(ParsePatternError::PatternTooLong)
+    /// ));
+    /// ```
+    /// # Errors
+    /// Returns an error if bytes is longer than [`BYTES`] or all bytes are
+    /// masked as wildcards.
+    #[inline]
+    pub fn try_from_slice(bytes: &[u8], mask: u64) -> Result<Self, ParsePatternError> {
+        if bytes.len() > BYTES {
+            return Err(ParsePatternError::PatternTooLong);
+        }
+        let mask = u64::MAX.checked_shr(bytes.len() as u32).unwrap_or(0).not() & mask;
+        let mut input: [u8; BYTES] = [0; BYTES];
+        for (index, (input, &byte)) in input.iter_mut().zip(bytes).enumerate() {
+            // Wildcards are 0, like in parsed patterns.
+            if mask & (1 << (u64::BITS as usize - 1 - index)) != 0 {
+                *input = byte;
+            }
+        }
+        Self::from_parts(
+            Simd::from_array(input),
+            Mask::from_bitmask(mask.reverse_bits()),
+        )
+    }
 
-        Self {
+    /// Picks the anchors of a pattern, lanes past its length have to be
+    /// wildcards.
+    fn from_parts(
+        bytes: Simd<u8, BYTES>,
+        mask: Mask<i8, BYTES>,
+    ) -> Result<Self, ParsePatternError> {
+        let (first_offset, second_offset) =
+            frequency::anchors(&bytes, &mask, frequency::built_in_rank)
+                .ok_or(ParsePatternError::MissingNonWildcardByte)?;
+
+        Ok(Self {
             bytes,
             mask,
-            wildcard_prefix,
-            first_byte,
-        }
+            first_offset,
+            first_byte: Simd::splat(bytes[first_offset]),
+            second_offset,
+            second_byte: bytes[second_offset],
+            prefilter: Prefilter::for_mask(&mask),
+        })
+    }
+
+    /// Selects how candidate positions are found, see [`Prefilter`].
+    /// Patterns with a single non-wildcard byte always compare just that one.
+    /// ```
+    /// # use patterns::{Pattern, Prefilter};
+    /// let pattern = Pattern::new("00 ? 01");
+    /// assert_eq!(pattern.prefilter(), Prefilter::FirstByte);
+    /// let pattern = pattern.with_prefilter(Prefilter::TwoBytes);
+    /// let data = [0, 0, 0, 0, 1, 0, 0];
+    /// assert_eq!(pattern.matches(&data).collect::<Vec<_>>(), [2]);
+    /// ```
+    #[inline]
+    pub fn with_prefilter(mut self, prefilter: Prefilter) -> Self {
+        self.prefilter = prefilter;
+        self
+    }
+
+    /// The selected [`Prefilter`].
+    #[inline]
+    pub const fn prefilter(&self) -> Prefilter {
+        self.prefilter
+    }
+
+    /// The bytes of the pattern up to the last non-wildcard byte. Wildcards
+    /// are 0.
+    /// Together with [`Pattern::mask`], this recreates the pattern through
+    /// [`Pattern::from_slice`].
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 ? 03 ?");
+    /// assert_eq!(pattern.bytes(), [1, 0, 3]);
+    /// let copy = Pattern::from_slice(pattern.bytes(), pattern.mask());
+    /// assert_eq!(copy.matches(&[1, 2, 3]).count(), 1);
+    /// ```
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes.as_array()[..self.len()]
+    }
+
+    /// The mask of the pattern in the bitencoding of [`Pattern::from_slice`],
+    /// the most significant bit is the first byte.
+    /// ```
+    /// # use patterns::Pattern;
+    /// assert_eq!(Pattern::new("01 ? 03").mask(), 0b101 << 61);
+    /// ```
+    #[inline]
+    pub fn mask(&self) -> u64 {
+        self.mask.to_bitmask().reverse_bits()
     }
 
     /// Creates an iterator through data.
@@ -195,6 +991,314 @@ impl Pattern {
     ) -> Scanner<'pattern, 'data, 'cursor> {
         Scanner::new(self, data)
     }
+
+    /// Creates an iterator through data that stops after `max` matches.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01");
+    /// let mut limited = pattern.matches_limited(&[1, 0, 1, 1], 2);
+    /// assert_eq!(limited.by_ref().count(), 2);
+    /// assert!(limited.limit_reached());
+    /// ```
+    #[inline]
+    pub fn matches_limited<'pattern, 'data: 'cursor, 'cursor>(
+        &'pattern self,
+        data: &'data [u8],
+        max: usize,
+    ) -> Limited<'pattern, 'data, 'cursor> {
+        Limited {
+            scanner: self.matches(data),
+            remaining: max,
+            exhausted: false,
+        }
+    }
+
+    /// Finds the first match in data.
+    #[inline]
+    pub fn find(&self, data: &[u8]) -> Option<usize> {
+        // Short data and matches close to the start are common. Check the first
+        // chunk without setting up a scanner and its buffer.
+        let last = data.len().checked_sub(self.len())?;
+        let candidates = self.candidates(data, 0) & first_bits(last + 1);
+        if let Some(index) = Offsets::new(candidates).find(|&index| self.verify(data, index)) {
+            return Some(index);
+        }
+        if last < BYTES {
+            return None;
+        }
+        let mut scanner = self.matches(data);
+        scanner.skip_to(BYTES);
+        scanner.next()
+    }
+
+    /// Checks whether the pattern occurs anywhere in data.
+    /// Stops at the first verified candidate without keeping any scanner
+    /// state around.
+    #[inline]
+    pub fn exists(&self, data: &[u8]) -> bool {
+        let Some(last) = data.len().checked_sub(self.len()) else {
+            return false;
+        };
+        let mut adaptive = Adaptive::new(self);
+        let mut base = 0;
+        while base <= last {
+            let candidates =
+                self.candidates_with(data, base, adaptive.prefilter) & first_bits(last + 1 - base);
+            if Offsets::new(candidates).any(|offset| self.verify(data, base + offset)) {
+                return true;
+            }
+            adaptive.record(candidates, 0);
+            base += BYTES;
+        }
+        false
+    }
+
+    /// Writes the offsets of the first matches in data into `results`, until
+    /// either data or `results` runs out. Returns the number of offsets
+    /// written.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 01");
+    /// let mut results = [0; 4];
+    /// let found = pattern.find_all_into(&[1, 1, 1, 0, 1, 1], &mut results);
+    /// assert_eq!(&results[..found], &[0, 1, 4]);
+    /// ```
+    #[inline]
+    pub fn find_all_into(&self, data: &[u8], results: &mut [usize]) -> usize {
+        self.matches(data).collect_into_slice(results).0
+    }
+
+    /// Checks whether the pattern matches data at `offset`.
+    /// Returns `false` if the match would not fit into data.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 ? 03");
+    /// assert!(pattern.matches_at(&[0, 1, 2, 3], 1));
+    /// assert!(!pattern.matches_at(&[0, 1, 2, 3], 2));
+    /// ```
+    #[inline]
+    pub fn matches_at(&self, data: &[u8], offset: usize) -> bool {
+        match offset.checked_add(self.len()) {
+            Some(end) if end <= data.len() => self.verify(data, offset),
+            _ => false,
+        }
+    }
+
+    /// Finds the last match in data.
+    /// Scans from the end of data towards the start, which is much faster than
+    /// walking every match from the front when matches are sparse.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 ? 03");
+    /// let data = [1, 2, 3, 0, 1, 0, 3, 1, 2];
+    /// assert_eq!(pattern.rfind(&data), Some(4));
+    /// ```
+    #[inline]
+    pub fn rfind(&self, data: &[u8]) -> Option<usize> {
+        self.rmatches_from(data, data.len()).next()
+    }
+
+    /// Finds the match that starts closest to `pivot`.
+    /// Searches outwards in both directions, so only the data around the
+    /// closest match is scanned. On a tie, the match after `pivot` wins.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 02");
+    /// let data = [1, 2, 0, 0, 0, 0, 1, 2, 0, 1, 2];
+    /// assert_eq!(pattern.find_nearest(&data, 4), Some(6));
+    /// assert_eq!(pattern.find_nearest(&data, 2), Some(0));
+    /// ```
+    #[inline]
+    pub fn find_nearest(&self, data: &[u8], pivot: usize) -> Option<usize> {
+        let pivot = pivot.min(data.len());
+        let overlap = self.len() - 1;
+        // Searches the distances `near..far` on both sides, doubling every round.
+        let mut near = 0;
+        let mut far = BYTES;
+        loop {
+            let after = (pivot + near < data.len())
+                .then(|| {
+                    let start = pivot + near;
+                    let end = (pivot + far).saturating_add(overlap).min(data.len());
+                    self.find(&data[start..end]).map(|index| start + index)
+                })
+                .flatten();
+            // The pivot itself belongs to the matches after it.
+            let closest = near.max(1);
+            let before = (pivot >= closest)
+                .then(|| {
+                    let start = pivot.saturating_sub(far - 1);
+                    let end = (pivot - closest + overlap + 1).min(data.len());
+                    self.rfind(&data[start..end]).map(|index| start + index)
+                })
+                .flatten();
+
+            match (before, after) {
+                (Some(before), Some(after)) if pivot - before < after - pivot => {
+                    return Some(before)
+                }
+                (_, Some(after)) => return Some(after),
+                (Some(before), None) => return Some(before),
+                (None, None) if pivot + far >= data.len() && far > pivot => return None,
+                (None, None) => {}
+            }
+            near = far;
+            far = far.saturating_mul(2);
+        }
+    }
+
+    /// Counts all matches in data.
+    /// Works on whole chunks at a time instead of stopping at every match.
+    fn count(&self, data: &[u8]) -> usize {
+        let Some(last) = data.len().checked_sub(self.len()) else {
+            return 0;
+        };
+        let mut adaptive = Adaptive::new(self);
+        let mut count = 0;
+        let mut base = 0;
+        while base <= last {
+            let candidates =
+                self.candidates_with(data, base, adaptive.prefilter) & first_bits(last + 1 - base);
+            let matches = self.verified(data, base, candidates);
+            adaptive.record(candidates, matches);
+            count += matches.count_ones() as usize;
+            base += BYTES;
+        }
+        count
+    }
+
+    /// The number of bytes a match spans, i.e. up to the last non-wildcard
+    /// byte.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        (u64::BITS - self.mask.to_bitmask().leading_zeros()) as usize
+    }
+
+    /// Returns `true` if the pattern has only one non-wildcard byte.
+    #[inline]
+    pub(crate) fn is_single_byte(&self) -> bool {
+        self.first_offset == self.second_offset
+    }
+
+    /// The offset of the last byte `prefilter` compares.
+    #[inline]
+    fn reach(&self, prefilter: Prefilter) -> usize {
+        match prefilter {
+            Prefilter::FirstByte => self.first_offset,
+            Prefilter::TwoBytes => self.first_offset.max(self.second_offset),
+        }
+    }
+
+    /// Bitmask of the positions `base..base + BYTES` that pass the prefilter.
+    /// Bits of positions too close to the end of data are unspecified.
+    #[inline]
+    fn candidates(&self, data: &[u8], base: usize) -> u64 {
+        self.candidates_with(data, base, self.prefilter)
+    }
+
+    /// Bitmask of the positions `base..base + BYTES` that pass `prefilter`.
+    #[inline]
+    fn candidates_with(&self, data: &[u8], base: usize, prefilter: Prefilter) -> u64 {
+        let anchor = data.get(base + self.first_offset..).unwrap_or_default();
+        let mut candidates = load(anchor).simd_eq(self.first_byte).to_bitmask();
+        if prefilter == Prefilter::TwoBytes {
+            let anchor = data.get(base + self.second_offset..).unwrap_or_default();
+            candidates &= load(anchor)
+                .simd_eq(Simd::splat(self.second_byte))
+                .to_bitmask();
+        }
+        candidates
+    }
+
+    /// Narrows the `candidates` of the chunk at `base` down to the positions
+    /// that match.
+    fn verified(&self, data: &[u8], base: usize, candidates: u64) -> u64 {
+        if self.is_single_byte() {
+            return candidates;
+        }
+        let mut matches = 0;
+        for bit in Offsets::new(candidates) {
+            if self.verify(data, base + bit) {
+                matches |= 1 << bit;
+            }
+        }
+        matches
+    }
+
+    /// Checks the whole pattern at `index`. The caller must make sure that
+    /// `index + self.len() <= data.len()`.
+    #[inline]
+    fn verify(&self, data: &[u8], index: usize) -> bool {
+        load(&data[index..]).simd_eq(self.bytes).bitand(self.mask) == self.mask
+    }
+}
+
+/// Picks the prefilter while a scan runs. Starts out with the one of the
+/// pattern and moves on to [`Prefilter::TwoBytes`] once too many candidates
+/// turn out false, e.g. for a `00` anchor in zero-filled data.
+#[derive(Clone, Copy, Debug)]
+struct Adaptive {
+    prefilter: Prefilter,
+    // False candidates beyond the allowance, summed up over the chunks.
+    wasted: u32,
+}
+
+impl Adaptive {
+    /// False candidates per chunk that are not worth a second comparison.
+    const ALLOWANCE: u32 = 2;
+    /// Wasted verifications after which the prefilter switches.
+    const LIMIT: u32 = 4 * BYTES as u32;
+
+    #[inline]
+    const fn new(pattern: &Pattern) -> Self {
+        Self {
+            prefilter: pattern.prefilter,
+            wasted: 0,
+        }
+    }
+
+    /// Takes note of the candidates and matches of a chunk.
+    #[inline(always)]
+    fn record(&mut self, candidates: u64, matches: u64) {
+        if self.prefilter != Prefilter::FirstByte {
+            return;
+        }
+        let wasted = candidates.count_ones() - matches.count_ones();
+        self.wasted = (self.wasted + wasted).saturating_sub(Self::ALLOWANCE);
+        if self.wasted > Self::LIMIT {
+            self.prefilter = Prefilter::TwoBytes;
+        }
+    }
+}
+
+/// Drops the lowest `count` bits and moves the rest down.
+#[inline]
+fn shift_out(bits: u64, count: usize) -> u64 {
+    u32::try_from(count)
+        .ok()
+        .and_then(|count| bits.checked_shr(count))
+        .unwrap_or(0)
+}
+
+/// Bitmask with the lowest `count` bits set, saturating at [`BYTES`] bits.
+#[inline]
+const fn first_bits(count: usize) -> u64 {
+    if count >= BYTES {
+        u64::MAX
+    } else {
+        !(u64::MAX << count)
+    }
+}
+
+/// Loads the first `BYTES` bytes of data, padding with zeros if data is
+/// shorter.
+#[inline]
+fn load(data: &[u8]) -> Simd<u8, BYTES> {
+    if data.len() >= BYTES {
+        Simd::from_slice(data)
+    } else {
+        Simd::load_or_default(data)
+    }
 }
 
 impl FromStr for Pattern {
@@ -222,75 +1326,103 @@ impl FromStr for Pattern {
             mask[index] = true;
         }
 
-        let bytes = Simd::from_array(buffer);
-        let mask = Mask::from_array(mask);
-
-        let (wildcard_prefix, first_byte) = get_first_byte(&bytes, &mask, length)?;
-
-        Ok(Self {
-            bytes,
-            mask,
-            wildcard_prefix,
-            first_byte,
-        })
+        Self::from_parts(Simd::from_array(buffer), Mask::from_array(mask))
     }
 }
 
-fn get_first_byte(
-    bytes: &Simd<u8, BYTES>,
-    mask: &Mask<i8, BYTES>,
-    length: usize,
-) -> Result<(usize, Simd<u8, 64>), ParsePatternError> {
-    let wildcard_prefix = mask.first_set().unwrap_or(BYTES);
-    if wildcard_prefix >= length {
-        return Err(ParsePatternError::MissingNonWildcardByte);
+/// Writes the pattern in the syntax [`FromStr`] parses, e.g. `01 ? 03`.
+impl fmt::Display for Pattern {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mask = self.mask();
+        for (index, byte) in self.bytes().iter().enumerate() {
+            if index != 0 {
+                f.write_str(" ")?;
+            }
+            if mask & (1 << (u64::BITS as usize - 1 - index)) == 0 {
+                f.write_str("?")?;
+            } else {
+                write!(f, "{byte:02x}")?;
+            }
+        }
+        Ok(())
     }
-    let first_byte = Simd::splat(bytes[wildcard_prefix]);
-    Ok((wildcard_prefix, first_byte))
 }
 
 struct Buffer {
     // 3 * BYTES = 1x for rest of the data, 1x to not overrun,
     // 1x for weird patterns with a lot of prefix wildcards
     inner: [u8; 3 * BYTES],
-    in_use: bool,
 }
 
 impl Buffer {
     pub(crate) const fn new() -> Self {
         Self {
-            in_use: false,
             inner: [0_u8; 3 * BYTES],
         }
     }
 
     pub(crate) fn copy_from(&mut self, data: &[u8]) {
-        assert!(!self.in_use, "buffer reused");
-        self.in_use = true;
-
         let (data_stub, _) = self.inner.split_at_mut(data.len());
         data_stub.copy_from_slice(data);
     }
-
-    pub(crate) const fn in_use(&self) -> bool {
-        self.in_use
-    }
 }
 
 impl Deref for Buffer {
     type Target = [u8];
 
+    #[inline]
     fn deref(&self) -> &Self::Target {
         &self.inner
     }
 }
 
+/// The reasons a pattern can fail to parse.
+/// Implements [`Error`], so it works with `?` in functions returning boxed
+/// errors.
+/// ```
+/// # use patterns::Pattern;
+/// fn parse(pattern: &str) -> Result<Pattern, Box<dyn core::error::Error>> {
+///     Ok(pattern.parse()?)
+/// }
+/// assert_eq!(
+///     parse("zz").unwrap_err().to_string(),
+///     "invalid hex byte: invalid digit found in string"
+/// );
+/// ```
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum ParsePatternError {
     PatternTooLong,
     InvalidHexNumber(ParseIntError),
     MissingNonWildcardByte,
+    InvalidBackReference,
+    InvalidOffset(ParseIntError),
+}
+
+impl fmt::Display for ParsePatternError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PatternTooLong => write!(f, "pattern is longer than {BYTES} bytes"),
+            Self::InvalidHexNumber(error) => write!(f, "invalid hex byte: {error}"),
+            Self::MissingNonWildcardByte => f.write_str("pattern has no non-wildcard byte"),
+            Self::InvalidBackReference => {
+                f.write_str("back-reference to a capture that doesn't come before it")
+            }
+            Self::InvalidOffset(error) => write!(f, "invalid offset: {error}"),
+        }
+    }
+}
+
+impl Error for ParsePatternError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidHexNumber(error) | Self::InvalidOffset(error) => Some(error),
+            _ => None,
+        }
+    }
 }
 
 impl From<ParseIntError> for ParsePatternError {
@@ -0,0 +1,253 @@
+use crate::{
+    first_bits, load,
+    simd::{Simd, SimdPartialEq},
+    Pattern, BYTES,
+};
+
+/// An iterator searching several patterns in a single pass through data.
+/// Every chunk of data is loaded once and compared against the anchor byte of
+/// all patterns. Patterns are only checked individually in chunks that contain
+/// their anchor byte.
+///
+/// Yields `(pattern_index, offset)` pairs. Matches of the same pattern are
+/// yielded in ascending order, matches of different patterns can be
+/// interleaved.
+/// ```
+/// # use patterns::{MultiScanner, Pattern};
+/// let patterns = [Pattern::new("01 02"), Pattern::new("? 02 03")];
+/// let data = [0, 1, 2, 3];
+/// let found = MultiScanner::new(&patterns, &data).collect::<Vec<_>>();
+/// assert_eq!(found, [(0, 1), (1, 1)]);
+/// ```
+#[must_use]
+pub struct MultiScanner<'patterns, 'data> {
+    patterns: &'patterns [Pattern],
+    data: &'data [u8],
+    // The chunk of data starting at `base` that holds the anchor bytes.
+    chunk: Simd<u8, BYTES>,
+    base: usize,
+    next_base: usize,
+    // The pattern whose candidates are in `pending`.
+    pattern: usize,
+    pending: u64,
+    prefilter: Prefilter,
+    // The anchor bytes of the prefilter that occur in the current chunk.
+    present: ByteSet,
+}
+
+impl<'patterns, 'data> MultiScanner<'patterns, 'data> {
+    /// Create an iterator searching all `patterns` in data
+    #[inline]
+    pub fn new(patterns: &'patterns [Pattern], data: &'data [u8]) -> Self {
+        Self {
+            patterns,
+            data,
+            chunk: Simd::splat(0),
+            base: 0,
+            next_base: 0,
+            // Starts by loading the first chunk.
+            pattern: patterns.len(),
+            pending: 0,
+            prefilter: Prefilter::new(patterns),
+            present: ByteSet::new(),
+        }
+    }
+
+    /// Bitmask of the positions in the current chunk that hold the anchor byte
+    /// of `pattern`, limited to matches that fit into data.
+    fn anchors(&self, pattern: &Pattern) -> u64 {
+        if !self.present.contains(pattern.first_byte[0]) {
+            return 0;
+        }
+        let Some(last) = self.data.len().checked_sub(pattern.len()) else {
+            return 0;
+        };
+        // Anchors of matches that start within data, inclusive.
+        let low = pattern.first_offset;
+        let high = last + pattern.first_offset;
+        if high < self.base {
+            return 0;
+        }
+        let mut valid = first_bits(high - self.base + 1);
+        if low > self.base {
+            valid &= !first_bits(low - self.base);
+        }
+        self.chunk.simd_eq(pattern.first_byte).to_bitmask() & valid
+    }
+}
+
+impl<'patterns, 'data> Iterator for MultiScanner<'patterns, 'data> {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.pending != 0 {
+                let anchor = self.base + self.pending.trailing_zeros() as usize;
+                self.pending &= self.pending - 1;
+                let pattern = &self.patterns[self.pattern];
+                let index = anchor - pattern.first_offset;
+                if pattern.verify(self.data, index) {
+                    return Some((self.pattern, index));
+                }
+            }
+
+            self.pattern += 1;
+            if self.pattern >= self.patterns.len() {
+                // Skip all chunks that contain none of the anchor bytes.
+                loop {
+                    if self.next_base >= self.data.len() || self.patterns.is_empty() {
+                        return None;
+                    }
+                    self.base = self.next_base;
+                    self.next_base += BYTES;
+                    self.chunk = load(&self.data[self.base..]);
+                    self.present = self.prefilter.present(self.chunk);
+                    if !self.present.is_empty() {
+                        break;
+                    }
+                }
+                self.pattern = 0;
+            }
+            self.pending = self.anchors(&self.patterns[self.pattern]);
+        }
+    }
+}
+
+/// The distinct anchor bytes of several patterns.
+/// Patterns sharing an anchor byte share a single comparison per chunk.
+struct Prefilter {
+    bytes: [u8; 256],
+    len: usize,
+}
+
+impl Prefilter {
+    fn new(patterns: &[Pattern]) -> Self {
+        let mut seen = ByteSet::new();
+        let mut bytes = [0; 256];
+        let mut len = 0;
+        for pattern in patterns {
+            let byte = pattern.first_byte[0];
+            if !seen.contains(byte) {
+                seen.insert(byte);
+                bytes[len] = byte;
+                len += 1;
+            }
+        }
+        Self { bytes, len }
+    }
+
+    /// Returns which of the anchor bytes occur in chunk.
+    fn present(&self, chunk: Simd<u8, BYTES>) -> ByteSet {
+        let mut present = ByteSet::new();
+        for &byte in &self.bytes[..self.len] {
+            if chunk.simd_eq(Simd::splat(byte)).any() {
+                present.insert(byte);
+            }
+        }
+        present
+    }
+}
+
+/// A set of byte values
+#[derive(Clone, Copy)]
+struct ByteSet([u64; 4]);
+
+impl ByteSet {
+    const fn new() -> Self {
+        Self([0; 4])
+    }
+
+    fn insert(&mut self, byte: u8) {
+        self.0[usize::from(byte / 64)] |= 1 << (byte % 64);
+    }
+
+    const fn contains(&self, byte: u8) -> bool {
+        self.0[(byte / 64) as usize] & 1 << (byte % 64) != 0
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0 == [0; 4]
+    }
+}
+
+/// A set of patterns, each with an identifier, e.g. a signature database.
+/// ```
+/// # use patterns::{Pattern, PatternSet};
+/// let ids = ["first", "second"];
+/// let patterns = [Pattern::new("01 02"), Pattern::new("02 03")];
+/// let set = PatternSet::new(&ids, &patterns);
+/// let found = set.matches(&[1, 2, 3]).collect::<Vec<_>>();
+/// assert_eq!(found, [(&"first", 0), (&"second", 1)]);
+/// ```
+#[derive(Debug)]
+pub struct PatternSet<'set, Id> {
+    ids: &'set [Id],
+    patterns: &'set [Pattern],
+}
+
+impl<'set, Id> PatternSet<'set, Id> {
+    /// Pairs every id with the pattern at the same index.
+    /// # Panics
+    /// Panics if `ids` and `patterns` differ in length.
+    #[inline]
+    pub fn new(ids: &'set [Id], patterns: &'set [Pattern]) -> Self {
+        assert_eq!(ids.len(), patterns.len(), "every pattern needs an id");
+        Self { ids, patterns }
+    }
+
+    /// The number of patterns in the set.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns `true` if the set has no patterns.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Iterates over all ids and their patterns.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&'set Id, &'set Pattern)> {
+        self.ids.iter().zip(self.patterns)
+    }
+
+    /// Creates an iterator through data that searches all patterns in a single
+    /// pass, see [`MultiScanner`].
+    #[inline]
+    pub fn matches<'data>(&self, data: &'data [u8]) -> SetScanner<'set, 'data, Id> {
+        SetScanner {
+            ids: self.ids,
+            scanner: MultiScanner::new(self.patterns, data),
+        }
+    }
+}
+
+impl<'set, Id> Clone for PatternSet<'set, Id> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'set, Id> Copy for PatternSet<'set, Id> {}
+
+/// An iterator searching a [`PatternSet`] in data
+#[must_use]
+pub struct SetScanner<'set, 'data, Id> {
+    ids: &'set [Id],
+    scanner: MultiScanner<'set, 'data>,
+}
+
+impl<'set, 'data, Id> Iterator for SetScanner<'set, 'data, Id> {
+    type Item = (&'set Id, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.scanner
+            .next()
+            .map(|(pattern, index)| (&self.ids[pattern], index))
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512vbmi2", not(miri)))]
+use crate::BYTES;
+
+/// Iterates over the positions of the set bits of a candidate mask in
+/// ascending order.
+/// With AVX-512 VBMI2, all positions are extracted at once with `vpcompressb`
+/// and walked linearly instead of scanning for one bit after another.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512vbmi2", not(miri)))]
+pub(crate) struct Offsets {
+    offsets: [u8; BYTES],
+    index: usize,
+    len: usize,
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512vbmi2", not(miri)))]
+impl Offsets {
+    #[inline]
+    pub(crate) fn new(mask: u64) -> Self {
+        use core::{
+            arch::x86_64::{__m512i, _mm512_maskz_compress_epi8},
+            mem::transmute,
+        };
+
+        const INDICES: [u8; BYTES] = {
+            let mut indices = [0; BYTES];
+            let mut index = 0;
+            while index < BYTES {
+                indices[index] = index as u8;
+                index += 1;
+            }
+            indices
+        };
+
+        // Safety: Both types are 64 bytes that are valid for any bit pattern.
+        let indices = unsafe { transmute::<[u8; BYTES], __m512i>(INDICES) };
+        // Moves the index of every set bit to the front, in order.
+        // Safety: The target feature is enabled at compile time.
+        let offsets = unsafe { _mm512_maskz_compress_epi8(mask, indices) };
+        Self {
+            // Safety: Both types are 64 bytes that are valid for any bit pattern.
+            offsets: unsafe { transmute::<__m512i, [u8; BYTES]>(offsets) },
+            index: 0,
+            len: mask.count_ones() as usize,
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512vbmi2", not(miri)))]
+impl Iterator for Offsets {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.len {
+            return None;
+        }
+        let offset = self.offsets[self.index];
+        self.index += 1;
+        Some(usize::from(offset))
+    }
+}
+
+/// Iterates over the positions of the set bits of a candidate mask in
+/// ascending order.
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512vbmi2", not(miri))))]
+pub(crate) struct Offsets(u64);
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512vbmi2", not(miri))))]
+impl Offsets {
+    #[inline]
+    pub(crate) const fn new(mask: u64) -> Self {
+        Self(mask)
+    }
+}
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512vbmi2", not(miri))))]
+impl Iterator for Offsets {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let offset = self.0.trailing_zeros() as usize;
+        // Clear the lowest set bit.
+        self.0 &= self.0 - 1;
+        Some(offset)
+    }
+}
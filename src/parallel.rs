@@ -0,0 +1,37 @@
+use rayon::prelude::*;
+
+use crate::Pattern;
+
+/// How many bytes each task scans at most.
+const CHUNK: usize = 1024 * 1024;
+
+impl Pattern {
+    /// Scans data on the rayon thread pool.
+    /// Data is split with [`Pattern::split_points`], so matches across chunk
+    /// boundaries are found exactly once. Collecting the iterator yields the
+    /// matches in ascending order.
+    /// ```
+    /// # use patterns::Pattern;
+    /// use rayon::prelude::*;
+    ///
+    /// let pattern = Pattern::new("01 02");
+    /// let mut data = vec![0; 3_000_000];
+    /// data[1_048_575..1_048_577].copy_from_slice(&[1, 2]);
+    /// let found: Vec<_> = pattern.par_matches(&data).collect();
+    /// assert_eq!(found, [1_048_575]);
+    /// ```
+    #[inline]
+    pub fn par_matches<'pattern: 'data, 'data>(
+        &'pattern self,
+        data: &'data [u8],
+    ) -> impl ParallelIterator<Item = usize> + 'data {
+        let splits = self.split_points(data, data.len().div_ceil(CHUNK));
+        (0..splits.len())
+            .into_par_iter()
+            .flat_map_iter(move |chunk| {
+                let range = splits.range(chunk);
+                let start = range.start;
+                self.matches(&data[range]).map(move |index| start + index)
+            })
+    }
+}
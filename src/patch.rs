@@ -0,0 +1,203 @@
+use core::str::FromStr;
+
+use crate::{ParsePatternError, Pattern, Scanner, BYTES};
+
+/// Bytes to write over a match, see [`Pattern::replace`].
+/// Uses the same syntax as [`Pattern`], wildcards keep the original byte.
+/// ```
+/// # use patterns::Replacement;
+/// let replacement = Replacement::new("90 ? 90");
+/// let mut data = [1, 2, 3];
+/// replacement.apply(&mut data);
+/// assert_eq!(data, [0x90, 2, 0x90]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replacement {
+    bytes: [u8; BYTES],
+    // The bytes to write, in the bitencoding of `Pattern::from_slice`.
+    mask: u64,
+    len: usize,
+}
+
+impl Replacement {
+    /// Parse a replacement. Use the [`FromStr`] impl to return an error instead
+    /// of panicking.
+    /// # Panics
+    /// Panics if [`ParsePatternError`] is returned.
+    #[inline]
+    pub fn new(replacement: &str) -> Self {
+        replacement.parse().unwrap()
+    }
+
+    /// Create a replacement from a byte slice and a mask, like
+    /// [`Pattern::from_slice`]. A 0 bit keeps the original byte.
+    /// # Panics
+    /// Panics if bytes is longer than [`BYTES`].
+    #[inline]
+    pub fn from_slice(bytes: &[u8], mask: u64) -> Self {
+        assert!(
+            bytes.len() <= BYTES,
+            "replacement is longer than {BYTES} bytes"
+        );
+        let mut buffer = [0; BYTES];
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        Self {
+            bytes: buffer,
+            mask,
+            len: bytes.len(),
+        }
+    }
+
+    /// The number of bytes the replacement spans.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the replacement doesn't span any bytes.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Writes the replacement to the start of `data`, keeping the bytes at
+    /// wildcards.
+    /// # Panics
+    /// Panics if data is shorter than the replacement.
+    #[inline]
+    pub fn apply(&self, data: &mut [u8]) {
+        let data = &mut data[..self.len];
+        for (index, (byte, replacement)) in data.iter_mut().zip(self.bytes).enumerate() {
+            if self.mask & (1 << (u64::BITS as usize - 1 - index)) != 0 {
+                *byte = replacement;
+            }
+        }
+    }
+}
+
+impl FromStr for Replacement {
+    type Err = ParsePatternError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = Pattern::from_str(s)?;
+        Ok(Self::from_slice(pattern.bytes(), pattern.mask()))
+    }
+}
+
+impl Pattern {
+    /// Writes `replacement` over every match in data and returns how many
+    /// matches were patched.
+    /// Matches are found in the original data: the search continues behind
+    /// the longer of the match and the replacement, so patches never overlap.
+    /// Matches too close to the end of data to fit the replacement are left
+    /// alone. [`Pattern::plan_replace`] lists the same edits without writing
+    /// them.
+    /// ```
+    /// # use patterns::{Pattern, Replacement};
+    /// let pattern = Pattern::new("e8 ? ? ? ?");
+    /// let replacement = Replacement::new("90 90 90 90 90");
+    /// let mut data = [0, 0xe8, 1, 2, 3, 4, 0xe8, 1];
+    /// assert_eq!(pattern.replace(&mut data, &replacement), 1);
+    /// assert_eq!(data, [0, 0x90, 0x90, 0x90, 0x90, 0x90, 0xe8, 1]);
+    /// ```
+    #[inline]
+    pub fn replace(&self, data: &mut [u8], replacement: &Replacement) -> usize {
+        let step = self.len().max(replacement.len());
+        let mut start = 0;
+        let mut patched = 0;
+        while let Some(index) = self.find(&data[start..]) {
+            let offset = start + index;
+            let Some(target) = data.get_mut(offset..offset + replacement.len()) else {
+                // Later matches don't fit either.
+                break;
+            };
+            replacement.apply(target);
+            patched += 1;
+            start = offset + step;
+        }
+        patched
+    }
+
+    /// Lists the edits [`Pattern::replace`] would make, without writing them.
+    /// ```
+    /// # use patterns::{Pattern, Replacement};
+    /// let pattern = Pattern::new("01 02");
+    /// let replacement = Replacement::new("? ff");
+    /// let data = [1, 2, 0, 1, 2];
+    /// let edit = pattern.plan_replace(&data, &replacement).last().unwrap();
+    /// assert_eq!(edit.offset(), 3);
+    /// assert_eq!(edit.original(), [1, 2]);
+    /// assert_eq!(edit.patched(), [1, 0xff]);
+    /// ```
+    #[inline]
+    pub fn plan_replace<'pattern, 'data, 'replacement>(
+        &'pattern self,
+        data: &'data [u8],
+        replacement: &'replacement Replacement,
+    ) -> Edits<'pattern, 'data, 'replacement> {
+        Edits {
+            data,
+            replacement,
+            step: self.len().max(replacement.len()),
+            scanner: self.matches(data),
+        }
+    }
+}
+
+/// An edit planned by [`Pattern::plan_replace`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit<'data> {
+    offset: usize,
+    original: &'data [u8],
+    patched: [u8; BYTES],
+}
+
+impl<'data> Edit<'data> {
+    /// Where the edit starts within data
+    #[inline]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The bytes the edit overwrites.
+    #[inline]
+    pub const fn original(&self) -> &'data [u8] {
+        self.original
+    }
+
+    /// The bytes after the edit.
+    #[inline]
+    pub fn patched(&self) -> &[u8] {
+        &self.patched[..self.original.len()]
+    }
+}
+
+/// An iterator through the edits of a replacement, see
+/// [`Pattern::plan_replace`]
+#[must_use]
+pub struct Edits<'pattern, 'data, 'replacement> {
+    data: &'data [u8],
+    replacement: &'replacement Replacement,
+    step: usize,
+    scanner: Scanner<'pattern, 'data, 'data>,
+}
+
+impl<'pattern, 'data, 'replacement> Iterator for Edits<'pattern, 'data, 'replacement> {
+    type Item = Edit<'data>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.scanner.next()?;
+        let original = self.data.get(offset..offset + self.replacement.len())?;
+        self.scanner.skip_to(offset + self.step);
+        let mut patched = [0; BYTES];
+        patched[..original.len()].copy_from_slice(original);
+        self.replacement.apply(&mut patched);
+        Some(Edit {
+            offset,
+            original,
+            patched,
+        })
+    }
+}
@@ -0,0 +1,107 @@
+use core::{error::Error, fmt, mem};
+
+/// Memory that pointers can be read from, e.g. a slice or another process.
+pub trait DataSource {
+    /// The error returned if memory can't be read.
+    type Error;
+
+    /// Fills buffer with the memory at `address`.
+    /// # Errors
+    /// Returns an error if not all of buffer could be read.
+    fn read_exact_at(&self, address: usize, buffer: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// Addresses are offsets within the slice.
+impl DataSource for [u8] {
+    type Error = OutOfBounds;
+
+    #[inline]
+    fn read_exact_at(&self, address: usize, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let end = address.checked_add(buffer.len()).ok_or(OutOfBounds)?;
+        buffer.copy_from_slice(self.get(address..end).ok_or(OutOfBounds)?);
+        Ok(())
+    }
+}
+
+/// Returned by the [`DataSource`] of a slice for reads past its end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfBounds;
+
+impl fmt::Display for OutOfBounds {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("read past the end of the data")
+    }
+}
+
+impl Error for OutOfBounds {}
+
+/// Offsets to follow from an address to the value a multilevel pointer
+/// points to, e.g. from a global found with a signature to a field of an
+/// object it references.
+///
+/// Every offset but the last is added to the address and the pointer stored
+/// there is read as the next address. The last offset is added to the final
+/// pointer without reading. Pointers are read in little endian byte order.
+/// ```
+/// # use patterns::PointerChain;
+/// let mut data = [0; 32];
+/// // A pointer at 4 to 16, and one at 16 + 8 to 2.
+/// data[4..12].copy_from_slice(&16_u64.to_le_bytes());
+/// data[24..32].copy_from_slice(&2_u64.to_le_bytes());
+/// let chain = PointerChain::new(&[4, 8, 6]);
+/// assert_eq!(chain.resolve(&data[..], 0), Ok(8));
+/// assert!(chain.resolve(&data[..], 28).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointerChain<'offsets> {
+    offsets: &'offsets [isize],
+    width: usize,
+}
+
+impl<'offsets> PointerChain<'offsets> {
+    /// Creates a chain of pointers of the native width.
+    #[inline]
+    pub const fn new(offsets: &'offsets [isize]) -> Self {
+        Self {
+            offsets,
+            width: mem::size_of::<usize>(),
+        }
+    }
+
+    /// Reads pointers of `width` bytes instead, e.g. 4 for a 32-bit process.
+    /// # Panics
+    /// Panics if `width` is neither 4 nor 8.
+    #[inline]
+    #[must_use]
+    pub const fn width(mut self, width: usize) -> Self {
+        assert!(width == 4 || width == 8, "pointers are 4 or 8 bytes wide");
+        self.width = width;
+        self
+    }
+
+    /// Follows the chain from `address` and returns the final address.
+    /// Wraps around like pointer arithmetic, a chain that leads nowhere fails
+    /// on the read instead.
+    /// # Errors
+    /// Returns the error of the first pointer that can't be read.
+    #[inline]
+    pub fn resolve<S>(&self, source: &S, address: usize) -> Result<usize, S::Error>
+    where
+        S: DataSource + ?Sized,
+    {
+        let Some((&last, pointers)) = self.offsets.split_last() else {
+            return Ok(address);
+        };
+        let mut address = address;
+        for &offset in pointers {
+            let mut pointer = [0; 8];
+            source.read_exact_at(
+                address.wrapping_add_signed(offset),
+                &mut pointer[..self.width],
+            )?;
+            address = u64::from_le_bytes(pointer) as usize;
+        }
+        Ok(address.wrapping_add_signed(last))
+    }
+}
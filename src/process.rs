@@ -0,0 +1,888 @@
+use core::ptr;
+use std::{boxed::Box, collections::VecDeque, io, path::PathBuf, vec, vec::Vec};
+
+#[cfg(any(not(windows), feature = "windows"))]
+use crate::DataSource;
+use crate::{Pattern, StreamScanner};
+
+/// A mapped region of memory of a process
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Region {
+    /// The address of the first byte
+    pub base: usize,
+    /// The number of bytes
+    pub len: usize,
+    /// Whether the region can be read
+    pub readable: bool,
+    /// Whether the region can be written
+    pub writable: bool,
+    /// Whether the region can be executed
+    pub executable: bool,
+}
+
+impl Region {
+    /// Lists the mapped regions of the current process in ascending order.
+    /// Pages the kernel provides for its own use and that fault on some reads,
+    /// like `[vvar]` on Linux, are left out.
+    /// # Errors
+    /// Returns the error of reading the memory map of the process.
+    #[inline]
+    pub fn current_process() -> io::Result<Vec<Self>> {
+        platform::current_process()
+    }
+
+    /// The address behind the last byte.
+    #[inline]
+    pub const fn end(&self) -> usize {
+        self.base + self.len
+    }
+}
+
+/// A module loaded into the current process, e.g. the executable or a shared
+/// library
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ModuleInfo {
+    /// The file the module was loaded from, empty if it is unknown
+    pub path: PathBuf,
+    /// The address the module is loaded at
+    pub base: usize,
+    /// The number of bytes from the base to the end of the module
+    pub len: usize,
+}
+
+impl ModuleInfo {
+    /// Lists the modules loaded into the current process, the executable
+    /// first. Uses `dl_iterate_phdr` on Linux and `EnumProcessModules` on
+    /// Windows.
+    /// # Errors
+    /// Returns the error of listing the modules.
+    #[inline]
+    pub fn current_process() -> io::Result<Vec<Self>> {
+        platform::modules()
+    }
+
+    /// The address behind the last byte.
+    #[inline]
+    pub const fn end(&self) -> usize {
+        self.base + self.len
+    }
+}
+
+impl Pattern {
+    /// Creates an iterator through all readable memory of the current process.
+    /// Yields the base address of the region and the offset of the match
+    /// within it. Matches never span regions.
+    /// Memory is copied in chunks before it is searched, because the
+    /// iterator itself writes to the stack and the heap while it runs.
+    /// The pattern itself lies in memory as well, so it matches at least once,
+    /// unless it only lives in registers.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let needle = Box::new(*b"patterns-own-process");
+    /// let pattern = Pattern::from_slice(&needle[..], u64::MAX);
+    /// let address = needle.as_ptr() as usize;
+    /// // Safety: Only this thread runs and it changes no mappings.
+    /// let mut found = unsafe { pattern.matches_own_process() }.unwrap();
+    /// assert!(found.any(|(base, offset)| base + offset == address));
+    /// ```
+    /// # Errors
+    /// Returns the error of reading the memory map of the process.
+    /// # Safety
+    /// No region may be unmapped or made unreadable while the iterator runs,
+    /// e.g. by another thread freeing a large allocation. File mappings must
+    /// not shrink below their mapped length.
+    /// No other thread may write to any readable memory while the iterator
+    /// runs, reading memory that is written at the same time is a data race.
+    #[inline]
+    pub unsafe fn matches_own_process(&self) -> io::Result<OwnProcessMatches<'_>> {
+        let mut regions = Region::current_process()?;
+        regions.retain(|region| region.readable);
+        Ok(OwnProcessMatches {
+            regions: regions.into_iter(),
+            memory: OwnMemory::new(self),
+        })
+    }
+}
+
+/// An iterator through the memory of the current process, see
+/// [`Pattern::matches_own_process`]
+#[must_use]
+pub struct OwnProcessMatches<'pattern> {
+    regions: vec::IntoIter<Region>,
+    memory: OwnMemory<'pattern>,
+}
+
+impl<'pattern> Iterator for OwnProcessMatches<'pattern> {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Safety: The caller of `matches_own_process` keeps the region mapped
+            // and readable.
+            if let Some(offset) = unsafe { self.memory.next() } {
+                return Some((self.memory.address, offset));
+            }
+            let region = self.regions.next()?;
+            self.memory.start(region.base, region.len);
+        }
+    }
+}
+
+/// Searches a range of memory of the current process through copies of it,
+/// so that no reference to memory that can change is ever created.
+struct OwnMemory<'pattern> {
+    pattern: &'pattern Pattern,
+    // The range the stream runs through and how much of it was copied.
+    address: usize,
+    position: usize,
+    len: usize,
+    stream: StreamScanner<'pattern>,
+    buffer: Box<[u8]>,
+    pending: VecDeque<u64>,
+}
+
+impl<'pattern> OwnMemory<'pattern> {
+    fn new(pattern: &'pattern Pattern) -> Self {
+        Self {
+            pattern,
+            address: 0,
+            position: 0,
+            len: 0,
+            stream: StreamScanner::new(pattern),
+            buffer: std::vec![0; CHUNK].into_boxed_slice(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Starts searching `len` bytes at `address`.
+    fn start(&mut self, address: usize, len: usize) {
+        self.address = address;
+        self.position = 0;
+        self.len = len;
+        self.stream = StreamScanner::new(self.pattern);
+    }
+
+    /// The offset of the next match in the range, or `None` once it is
+    /// searched completely.
+    /// # Safety
+    /// The range has to stay mapped and readable.
+    unsafe fn next(&mut self) -> Option<usize> {
+        loop {
+            if let Some(offset) = self.pending.pop_front() {
+                // The offset lies within the range.
+                return Some(offset as usize);
+            }
+            if self.position == self.len {
+                return None;
+            }
+            let len = (self.len - self.position).min(CHUNK);
+            let source = ptr::with_exposed_provenance::<u8>(self.address + self.position);
+            // Safety: The caller keeps the range readable. The buffer itself can
+            // lie in the range, so the copy may overlap.
+            unsafe { ptr::copy(source, self.buffer.as_mut_ptr(), len) };
+            self.pending.extend(self.stream.feed(&self.buffer[..len]));
+            self.position += len;
+        }
+    }
+}
+
+impl Pattern {
+    /// Creates an iterator through the readable memory of modules of the
+    /// current process, e.g. all of [`ModuleInfo::current_process`] or only
+    /// the executable. Only executable memory, i.e. code, is searched if
+    /// `executable` is set.
+    /// Yields the module and the offset of the match from its base, which
+    /// stays the same when the module is loaded elsewhere. Matches span
+    /// adjacent regions of a module but never modules.
+    /// Like [`Pattern::matches_own_process`], memory is copied in chunks
+    /// before it is searched.
+    /// ```
+    /// # use patterns::{ModuleInfo, Pattern};
+    /// let modules = ModuleInfo::current_process().unwrap();
+    /// let pattern = Pattern::from_slice(b"patterns-module-marker", u64::MAX);
+    /// // Safety: Only this thread runs and it loads no libraries.
+    /// let mut found = unsafe { pattern.matches_modules(&modules, false) }.unwrap();
+    /// assert!(found.any(|(module, _)| module == &modules[0]));
+    /// // String literals are data, not code.
+    /// let in_code = unsafe { pattern.matches_modules(&modules, true) }.unwrap();
+    /// assert_eq!(in_code.count(), 0);
+    /// ```
+    /// # Errors
+    /// Returns the error of reading the memory map of the process.
+    /// # Safety
+    /// No module may be unloaded and no region of one made unreadable while
+    /// the iterator runs, see [`Pattern::matches_own_process`].
+    /// No other thread may write to the searched memory of the modules while
+    /// the iterator runs, e.g. to their global variables.
+    #[inline]
+    pub unsafe fn matches_modules<'scan>(
+        &'scan self,
+        modules: &'scan [ModuleInfo],
+        executable: bool,
+    ) -> io::Result<ModuleMatches<'scan>> {
+        let mut regions = Region::current_process()?;
+        regions.retain(|region| region.readable && (region.executable || !executable));
+        let mut ranges = Vec::<(usize, usize, usize)>::new();
+        for (index, module) in modules.iter().enumerate() {
+            for region in &regions {
+                let start = region.base.max(module.base);
+                let end = region.end().min(module.end());
+                if start >= end {
+                    continue;
+                }
+                match ranges.last_mut() {
+                    Some((last, base, len)) if *last == index && *base + *len == start => {
+                        *len += end - start;
+                    }
+                    _ => ranges.push((index, start, end - start)),
+                }
+            }
+        }
+        Ok(ModuleMatches {
+            modules,
+            ranges: ranges.into_iter(),
+            module: 0,
+            memory: OwnMemory::new(self),
+        })
+    }
+}
+
+/// An iterator through the modules of the current process, see
+/// [`Pattern::matches_modules`]
+#[must_use]
+pub struct ModuleMatches<'scan> {
+    modules: &'scan [ModuleInfo],
+    // The module, address and length of the memory left to search.
+    ranges: vec::IntoIter<(usize, usize, usize)>,
+    // The module the memory belongs to.
+    module: usize,
+    memory: OwnMemory<'scan>,
+}
+
+impl<'scan> Iterator for ModuleMatches<'scan> {
+    type Item = (&'scan ModuleInfo, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Safety: The caller of `matches_modules` keeps the module loaded and
+            // the range readable.
+            if let Some(offset) = unsafe { self.memory.next() } {
+                let module = &self.modules[self.module];
+                return Some((module, self.memory.address - module.base + offset));
+            }
+            let (module, address, len) = self.ranges.next()?;
+            self.module = module;
+            self.memory.start(address, len);
+        }
+    }
+}
+
+/// How many bytes of memory are copied or read at once.
+const CHUNK: usize = 64 * 1024;
+
+/// Another process whose memory is read through `/proc/<pid>/mem` on Linux
+/// and `ReadProcessMemory` on Windows, where it needs the `windows` feature.
+/// ```
+/// # use patterns::Process;
+/// let process = Process::open(std::process::id()).unwrap();
+/// let data = [1_u8, 2, 3];
+/// let mut buffer = [0; 3];
+/// process
+///     .read_at(data.as_ptr() as usize, &mut buffer)
+///     .unwrap();
+/// assert_eq!(buffer, data);
+/// ```
+#[cfg(any(not(windows), feature = "windows"))]
+#[derive(Debug)]
+pub struct Process {
+    pid: u32,
+    memory: platform::Memory,
+}
+
+#[cfg(any(not(windows), feature = "windows"))]
+impl Process {
+    /// Opens the memory of the process with the id `pid`.
+    /// Needs the same permissions as attaching a debugger.
+    /// # Errors
+    /// Returns the error of opening the memory of the process.
+    #[inline]
+    pub fn open(pid: u32) -> io::Result<Self> {
+        Ok(Self {
+            pid,
+            memory: platform::open(pid)?,
+        })
+    }
+
+    /// The id of the process.
+    #[inline]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Lists the mapped regions of the process in ascending order, see
+    /// [`Region::current_process`].
+    /// # Errors
+    /// Returns the error of reading the memory map of the process.
+    #[inline]
+    pub fn regions(&self) -> io::Result<Vec<Region>> {
+        platform::regions(self.pid, &self.memory)
+    }
+
+    /// Reads memory of the process starting at `address` into buffer and
+    /// returns how many bytes were read.
+    /// # Errors
+    /// Returns the error of reading, e.g. if the memory isn't mapped.
+    #[inline]
+    pub fn read_at(&self, address: usize, buffer: &mut [u8]) -> io::Result<usize> {
+        platform::read_at(&self.memory, address, buffer)
+    }
+}
+
+/// Addresses are virtual addresses in the process.
+#[cfg(any(not(windows), feature = "windows"))]
+impl DataSource for Process {
+    type Error = io::Error;
+
+    #[inline]
+    fn read_exact_at(&self, address: usize, mut buffer: &mut [u8]) -> io::Result<()> {
+        let mut address = address;
+        while !buffer.is_empty() {
+            match self.read_at(address, buffer)? {
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                read => {
+                    address += read;
+                    buffer = &mut buffer[read..];
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(not(windows), feature = "windows"))]
+impl Pattern {
+    /// Creates an iterator through the readable regions of another process
+    /// that `filter` accepts, e.g. only executable ones.
+    /// Memory is read in chunks and searched with a [`StreamScanner`], so
+    /// matches span chunks but never regions. Pages that can't be read are
+    /// skipped.
+    /// Yields the base address of the region and the offset of the match
+    /// within it, or the first error reading the process returns, after which
+    /// it stops.
+    /// ```
+    /// # use patterns::{Pattern, Process};
+    /// let needle = Box::new(*b"patterns-remote-process");
+    /// let pattern = Pattern::from_slice(&needle[..], u64::MAX);
+    /// let address = needle.as_ptr() as usize;
+    /// let process = Process::open(std::process::id()).unwrap();
+    /// let mut found = pattern
+    ///     .matches_process(&process, |region| region.writable)
+    ///     .unwrap();
+    /// assert!(found.any(|found| found.is_ok_and(|(base, offset)| base + offset == address)));
+    /// ```
+    /// # Errors
+    /// Returns the error of reading the memory map of the process.
+    #[inline]
+    pub fn matches_process<'scan, F>(
+        &'scan self,
+        process: &'scan Process,
+        mut filter: F,
+    ) -> io::Result<ProcessMatches<'scan>>
+    where
+        F: FnMut(&Region) -> bool,
+    {
+        let mut regions = process.regions()?;
+        regions.retain(|region| region.readable && filter(region));
+        Ok(ProcessMatches {
+            pattern: self,
+            process,
+            regions: regions.into_iter(),
+            base: 0,
+            position: 0,
+            len: 0,
+            stream: StreamScanner::new(self),
+            buffer: std::vec![0; CHUNK].into_boxed_slice(),
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
+}
+
+/// An iterator through the memory of another process, see
+/// [`Pattern::matches_process`]
+#[cfg(any(not(windows), feature = "windows"))]
+#[must_use]
+pub struct ProcessMatches<'scan> {
+    pattern: &'scan Pattern,
+    process: &'scan Process,
+    regions: vec::IntoIter<Region>,
+    // The region the stream runs through and how much of it was read.
+    base: usize,
+    position: usize,
+    len: usize,
+    stream: StreamScanner<'scan>,
+    buffer: Box<[u8]>,
+    pending: VecDeque<u64>,
+    done: bool,
+}
+
+#[cfg(any(not(windows), feature = "windows"))]
+impl<'scan> Iterator for ProcessMatches<'scan> {
+    type Item = io::Result<(usize, usize)>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        /// Unreadable memory is skipped in steps of the smallest page size.
+        const PAGE: usize = 4096;
+
+        loop {
+            if let Some(offset) = self.pending.pop_front() {
+                // The offset lies within a region of memory.
+                return Some(Ok((self.base, offset as usize)));
+            }
+            if self.done {
+                return None;
+            }
+            if self.position == self.len {
+                let region = self.regions.next()?;
+                self.base = region.base;
+                self.position = 0;
+                self.len = region.len;
+                self.stream = StreamScanner::new(self.pattern);
+                continue;
+            }
+            let len = (self.len - self.position).min(CHUNK);
+            match self
+                .process
+                .read_at(self.base + self.position, &mut self.buffer[..len])
+            {
+                // The region was unmapped since it was listed.
+                Ok(0) => self.position = self.len,
+                Ok(read) => {
+                    self.pending.extend(self.stream.feed(&self.buffer[..read]));
+                    self.position += read;
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) if platform::is_unreadable(&error) => {
+                    let skipped = len.min(PAGE);
+                    self.stream.skip(skipped as u64);
+                    self.position += skipped;
+                }
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod platform {
+    use core::ffi::{c_char, c_int, c_void, CStr};
+    use std::{
+        ffi::OsStr,
+        fs,
+        fs::File,
+        io,
+        os::unix::{ffi::OsStrExt, fs::FileExt},
+        path::PathBuf,
+        slice,
+        vec::Vec,
+    };
+
+    use super::{ModuleInfo, Region};
+
+    pub(super) type Memory = File;
+
+    /// The leading fields of `dl_phdr_info`.
+    #[repr(C)]
+    struct DlPhdrInfo {
+        address: usize,
+        name: *const c_char,
+        headers: *const ProgramHeader,
+        count: u16,
+    }
+
+    /// An `Elf64_Phdr` or `Elf32_Phdr`, which keep the flags elsewhere.
+    #[repr(C)]
+    struct ProgramHeader {
+        kind: u32,
+        #[cfg(target_pointer_width = "64")]
+        _flags: u32,
+        _offset: usize,
+        address: usize,
+        _physical_address: usize,
+        _file_size: usize,
+        memory_size: usize,
+        #[cfg(target_pointer_width = "32")]
+        _flags: u32,
+        _align: usize,
+    }
+
+    const PT_LOAD: u32 = 1;
+
+    extern "C" {
+        fn dl_iterate_phdr(
+            callback: unsafe extern "C" fn(*mut DlPhdrInfo, usize, *mut c_void) -> c_int,
+            data: *mut c_void,
+        ) -> c_int;
+    }
+
+    pub(super) fn modules() -> io::Result<Vec<ModuleInfo>> {
+        /// Adds the loaded segments of a module as one module.
+        unsafe extern "C" fn push(info: *mut DlPhdrInfo, _: usize, data: *mut c_void) -> c_int {
+            // Safety: `dl_iterate_phdr` passes a valid info and the data given
+            // to it.
+            let (info, modules) = unsafe { (&*info, &mut *data.cast::<Vec<ModuleInfo>>()) };
+            let headers = if info.headers.is_null() {
+                &[]
+            } else {
+                // Safety: The headers are valid for the count.
+                unsafe { slice::from_raw_parts(info.headers, usize::from(info.count)) }
+            };
+            let loaded = headers.iter().filter(|header| header.kind == PT_LOAD);
+            let start = loaded.clone().map(|header| header.address).min();
+            let end = loaded
+                .map(|header| header.address + header.memory_size)
+                .max();
+            if let Some((start, end)) = start.zip(end) {
+                let name = if info.name.is_null() {
+                    &[]
+                } else {
+                    // Safety: Names are null terminated.
+                    unsafe { CStr::from_ptr(info.name) }.to_bytes()
+                };
+                modules.push(ModuleInfo {
+                    path: PathBuf::from(OsStr::from_bytes(name)),
+                    base: info.address.wrapping_add(start),
+                    len: end - start,
+                });
+            }
+            0
+        }
+
+        let mut modules = Vec::<ModuleInfo>::new();
+        // Safety: The callback only touches the vector.
+        unsafe { dl_iterate_phdr(push, (&raw mut modules).cast()) };
+        // The executable comes first, without a name.
+        if let Some(executable) = modules
+            .first_mut()
+            .filter(|module| module.path.as_os_str().is_empty())
+        {
+            executable.path = std::env::current_exe().unwrap_or_default();
+        }
+        Ok(modules)
+    }
+
+    pub(super) fn current_process() -> io::Result<Vec<Region>> {
+        parse_maps(&fs::read_to_string("/proc/self/maps")?)
+    }
+
+    pub(super) fn open(pid: u32) -> io::Result<Memory> {
+        File::open(std::format!("/proc/{pid}/mem"))
+    }
+
+    pub(super) fn regions(pid: u32, _: &Memory) -> io::Result<Vec<Region>> {
+        parse_maps(&fs::read_to_string(std::format!("/proc/{pid}/maps"))?)
+    }
+
+    pub(super) fn read_at(memory: &Memory, address: usize, buffer: &mut [u8]) -> io::Result<usize> {
+        memory.read_at(buffer, address as u64)
+    }
+
+    /// Reading unmapped memory fails with `EIO`.
+    pub(super) fn is_unreadable(error: &io::Error) -> bool {
+        const EIO: i32 = 5;
+        error.raw_os_error() == Some(EIO)
+    }
+
+    /// Parses the lines of `/proc/<pid>/maps`, e.g.
+    /// `7f00-7f10 r-xp 00000000 08:01 1234 /usr/lib/libc.so.6`.
+    pub(super) fn parse_maps(maps: &str) -> io::Result<Vec<Region>> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed memory map");
+        let mut regions = Vec::new();
+        for line in maps.lines() {
+            let mut fields = line.split_ascii_whitespace();
+            let (range, permissions) = fields.next().zip(fields.next()).ok_or_else(invalid)?;
+            if fields.nth(3).is_some_and(|name| name.starts_with("[vvar")) {
+                continue;
+            }
+            let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+            let base = usize::from_str_radix(start, 16).map_err(|_| invalid())?;
+            let end = usize::from_str_radix(end, 16).map_err(|_| invalid())?;
+            let permissions = permissions.as_bytes();
+            regions.push(Region {
+                base,
+                len: end.checked_sub(base).ok_or_else(invalid)?,
+                readable: permissions.first() == Some(&b'r'),
+                writable: permissions.get(1) == Some(&b'w'),
+                executable: permissions.get(2) == Some(&b'x'),
+            });
+        }
+        Ok(regions)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use core::{
+        ffi::c_void,
+        mem::{size_of, MaybeUninit},
+    };
+    #[cfg(feature = "windows")]
+    use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle};
+    use std::{ffi::OsString, io, os::windows::ffi::OsStringExt, path::PathBuf, vec::Vec};
+
+    use super::{ModuleInfo, Region};
+
+    const MEM_COMMIT: u32 = 0x1000;
+    const PAGE_NOACCESS: u32 = 0x01;
+    const PAGE_GUARD: u32 = 0x100;
+    const WRITABLE: u32 = 0x04 | 0x08 | 0x40 | 0x80;
+    const EXECUTABLE: u32 = 0x10 | 0x20 | 0x40 | 0x80;
+
+    #[repr(C)]
+    struct MemoryBasicInformation {
+        base_address: *mut c_void,
+        _allocation_base: *mut c_void,
+        _allocation_protect: u32,
+        #[cfg(target_pointer_width = "64")]
+        _partition_id: u16,
+        region_size: usize,
+        state: u32,
+        protect: u32,
+        _kind: u32,
+    }
+
+    #[repr(C)]
+    struct ModuleInformation {
+        base: *mut c_void,
+        size: u32,
+        _entry_point: *mut c_void,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn VirtualQuery(
+            address: *const c_void,
+            buffer: *mut MemoryBasicInformation,
+            length: usize,
+        ) -> usize;
+        fn GetCurrentProcess() -> *mut c_void;
+        fn K32EnumProcessModules(
+            process: *mut c_void,
+            modules: *mut *mut c_void,
+            size: u32,
+            needed: *mut u32,
+        ) -> i32;
+        fn K32GetModuleInformation(
+            process: *mut c_void,
+            module: *mut c_void,
+            info: *mut ModuleInformation,
+            size: u32,
+        ) -> i32;
+        fn GetModuleFileNameW(module: *mut c_void, name: *mut u16, size: u32) -> u32;
+    }
+
+    pub(super) fn modules() -> io::Result<Vec<ModuleInfo>> {
+        const HANDLE: usize = size_of::<*mut c_void>();
+
+        // Safety: Only returns a pseudo handle, which needs no closing.
+        let process = unsafe { GetCurrentProcess() };
+        let mut handles = Vec::new();
+        loop {
+            let size = u32::try_from(handles.len() * HANDLE).unwrap_or(u32::MAX);
+            let mut needed = 0;
+            // Safety: The buffer is valid for its size.
+            let success =
+                unsafe { K32EnumProcessModules(process, handles.as_mut_ptr(), size, &mut needed) };
+            if success == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // Libraries can be loaded between the calls.
+            let count = needed as usize / HANDLE;
+            if needed <= size {
+                handles.truncate(count);
+                break;
+            }
+            handles.resize(count, std::ptr::null_mut());
+        }
+        handles
+            .into_iter()
+            .map(|handle| {
+                let mut info = MaybeUninit::<ModuleInformation>::uninit();
+                // Safety: The buffer is large enough for the information.
+                let success = unsafe {
+                    K32GetModuleInformation(
+                        process,
+                        handle,
+                        info.as_mut_ptr(),
+                        size_of::<ModuleInformation>() as u32,
+                    )
+                };
+                if success == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                // Safety: The call filled the buffer.
+                let info = unsafe { info.assume_init() };
+                Ok(ModuleInfo {
+                    path: module_path(handle)?,
+                    base: info.base.addr(),
+                    len: info.size as usize,
+                })
+            })
+            .collect()
+    }
+
+    /// The file a module was loaded from, in a buffer that grows until the
+    /// path isn't truncated.
+    fn module_path(module: *mut c_void) -> io::Result<PathBuf> {
+        let mut name = std::vec![0_u16; 260];
+        loop {
+            let size = u32::try_from(name.len()).unwrap_or(u32::MAX);
+            // Safety: The buffer is valid for its size.
+            let len = unsafe { GetModuleFileNameW(module, name.as_mut_ptr(), size) } as usize;
+            if len == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if len < name.len() {
+                return Ok(OsString::from_wide(&name[..len]).into());
+            }
+            name.resize(name.len() * 2, 0);
+        }
+    }
+
+    pub(super) fn current_process() -> io::Result<Vec<Region>> {
+        // Safety: The buffer is large enough for the information.
+        Ok(query(|address, info| unsafe {
+            VirtualQuery(address, info, size_of::<MemoryBasicInformation>())
+        }))
+    }
+
+    /// Walks the address space with a `VirtualQuery` like function, which
+    /// returns 0 past the highest address.
+    fn query(
+        mut query: impl FnMut(*const c_void, *mut MemoryBasicInformation) -> usize,
+    ) -> Vec<Region> {
+        let mut regions = Vec::new();
+        let mut address = 0_usize;
+        loop {
+            let mut info = MaybeUninit::<MemoryBasicInformation>::uninit();
+            if query(std::ptr::without_provenance(address), info.as_mut_ptr()) == 0 {
+                break;
+            }
+            // Safety: The query filled the buffer.
+            let info = unsafe { info.assume_init() };
+            if let Some(region) = region(&info) {
+                regions.push(region);
+            }
+            let next = info.base_address.addr().saturating_add(info.region_size);
+            if next <= address {
+                break;
+            }
+            address = next;
+        }
+        regions
+    }
+
+    /// The region described by `info`, if its memory is committed.
+    /// Guard pages fault on the first access and count as unreadable.
+    fn region(info: &MemoryBasicInformation) -> Option<Region> {
+        if info.state != MEM_COMMIT {
+            return None;
+        }
+        let protect = info.protect;
+        Some(Region {
+            base: info.base_address.addr(),
+            len: info.region_size,
+            readable: protect & (PAGE_NOACCESS | PAGE_GUARD) == 0 && protect != 0,
+            writable: protect & WRITABLE != 0,
+            executable: protect & EXECUTABLE != 0,
+        })
+    }
+
+    #[cfg(feature = "windows")]
+    pub(super) type Memory = OwnedHandle;
+
+    #[cfg(feature = "windows")]
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn OpenProcess(access: u32, inherit: i32, pid: u32) -> *mut c_void;
+        fn VirtualQueryEx(
+            process: *mut c_void,
+            address: *const c_void,
+            buffer: *mut MemoryBasicInformation,
+            length: usize,
+        ) -> usize;
+        fn ReadProcessMemory(
+            process: *mut c_void,
+            address: *const c_void,
+            buffer: *mut c_void,
+            size: usize,
+            read: *mut usize,
+        ) -> i32;
+    }
+
+    #[cfg(feature = "windows")]
+    pub(super) fn open(pid: u32) -> io::Result<Memory> {
+        const PROCESS_VM_READ: u32 = 0x0010;
+        const PROCESS_QUERY_INFORMATION: u32 = 0x0400;
+
+        // Safety: Only takes plain values.
+        let handle = unsafe { OpenProcess(PROCESS_VM_READ | PROCESS_QUERY_INFORMATION, 0, pid) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        // Safety: The handle is open and owned by nobody else.
+        Ok(unsafe { OwnedHandle::from_raw_handle(handle) })
+    }
+
+    #[cfg(feature = "windows")]
+    pub(super) fn regions(_: u32, memory: &Memory) -> io::Result<Vec<Region>> {
+        // Safety: The handle is open and the buffer is large enough for the
+        // information.
+        Ok(query(|address, info| unsafe {
+            VirtualQueryEx(
+                memory.as_raw_handle(),
+                address,
+                info,
+                size_of::<MemoryBasicInformation>(),
+            )
+        }))
+    }
+
+    #[cfg(feature = "windows")]
+    pub(super) fn read_at(memory: &Memory, address: usize, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        // Safety: The handle is open and buffer is valid for its length.
+        let success = unsafe {
+            ReadProcessMemory(
+                memory.as_raw_handle(),
+                std::ptr::without_provenance(address),
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                &mut read,
+            )
+        };
+        // Partial copies fail but still report what was read.
+        if success == 0 && read == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(read)
+    }
+
+    /// Reading uncommitted or protected memory fails with
+    /// `ERROR_PARTIAL_COPY` or `ERROR_NOACCESS`.
+    #[cfg(feature = "windows")]
+    pub(super) fn is_unreadable(error: &io::Error) -> bool {
+        const ERROR_PARTIAL_COPY: i32 = 299;
+        const ERROR_NOACCESS: i32 = 998;
+        matches!(
+            error.raw_os_error(),
+            Some(ERROR_PARTIAL_COPY | ERROR_NOACCESS)
+        )
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    boxed::Box,
+    collections::VecDeque,
+    fs::File,
+    io,
+    io::{BufRead, Read},
+    path::Path,
+    vec,
+};
+
+use crate::{Pattern, StreamScanner};
+
+/// How many bytes are read at once.
+const CHUNK: usize = 64 * 1024;
+
+impl Pattern {
+    /// Creates an iterator through everything a reader returns.
+    /// Yields the offsets of the matches from the start of the reader, or the
+    /// first error the reader returns, after which it stops.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 ? 03");
+    /// let reader = std::io::Cursor::new([0, 1, 2, 3, 1, 1, 3]);
+    /// let found = pattern
+    ///     .matches_reader(reader)
+    ///     .collect::<std::io::Result<Vec<_>>>();
+    /// assert_eq!(found.unwrap(), [1, 4]);
+    /// ```
+    #[inline]
+    pub fn matches_reader<R: Read>(&self, reader: R) -> ReaderMatches<'_, R> {
+        ReaderMatches {
+            stream: StreamScanner::new(self),
+            reader,
+            buffer: vec![0; CHUNK].into_boxed_slice(),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Creates an iterator through everything a buffered reader returns.
+    /// Searches the buffer of the reader in place instead of copying into a
+    /// buffer of its own, see [`Pattern::matches_reader`].
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 ? 03");
+    /// let reader = std::io::BufReader::with_capacity(2, &[0, 1, 2, 3, 1, 1, 3][..]);
+    /// let found = pattern
+    ///     .matches_buf_reader(reader)
+    ///     .collect::<std::io::Result<Vec<_>>>();
+    /// assert_eq!(found.unwrap(), [1, 4]);
+    /// ```
+    #[inline]
+    pub fn matches_buf_reader<R: BufRead>(&self, reader: R) -> BufReaderMatches<'_, R> {
+        BufReaderMatches {
+            stream: StreamScanner::new(self),
+            reader,
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Opens the file at path and creates an iterator through its contents,
+    /// see [`Pattern::matches_reader`].
+    /// # Errors
+    /// Returns the error of opening the file.
+    #[inline]
+    pub fn matches_file<P: AsRef<Path>>(&self, path: P) -> io::Result<ReaderMatches<'_, File>> {
+        Ok(self.matches_reader(File::open(path)?))
+    }
+
+    /// Finds the first match in the file at path, without reading the rest of
+    /// the file.
+    /// ```
+    /// # use patterns::Pattern;
+    /// # fn main() -> std::io::Result<()> {
+    /// let path = std::env::temp_dir().join("patterns-find-in-file");
+    /// std::fs::write(&path, [0, 1, 2])?;
+    /// assert_eq!(Pattern::new("01 02").find_in_file(&path)?, Some(1));
+    /// # std::fs::remove_file(path)
+    /// # }
+    /// ```
+    /// # Errors
+    /// Returns the first error of opening or reading the file.
+    #[inline]
+    pub fn find_in_file<P: AsRef<Path>>(&self, path: P) -> io::Result<Option<u64>> {
+        self.matches_file(path)?.next().transpose()
+    }
+}
+
+/// An iterator over the matches in a reader, see [`Pattern::matches_reader`]
+#[must_use]
+pub struct ReaderMatches<'pattern, R> {
+    stream: StreamScanner<'pattern>,
+    reader: R,
+    buffer: Box<[u8]>,
+    pending: VecDeque<u64>,
+    done: bool,
+}
+
+impl<'pattern, R: Read> Iterator for ReaderMatches<'pattern, R> {
+    type Item = io::Result<u64>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(index) = self.pending.pop_front() {
+                return Some(Ok(index));
+            }
+            if self.done {
+                return None;
+            }
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => self.done = true,
+                Ok(read) => self.pending.extend(self.stream.feed(&self.buffer[..read])),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+/// An iterator over the matches in a buffered reader, see
+/// [`Pattern::matches_buf_reader`]
+#[must_use]
+pub struct BufReaderMatches<'pattern, R> {
+    stream: StreamScanner<'pattern>,
+    reader: R,
+    pending: VecDeque<u64>,
+    done: bool,
+}
+
+impl<'pattern, R: BufRead> Iterator for BufReaderMatches<'pattern, R> {
+    type Item = io::Result<u64>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(index) = self.pending.pop_front() {
+                return Some(Ok(index));
+            }
+            if self.done {
+                return None;
+            }
+            match self.reader.fill_buf() {
+                Ok([]) => self.done = true,
+                Ok(buffer) => {
+                    let read = buffer.len();
+                    self.pending.extend(self.stream.feed(buffer));
+                    self.reader.consume(read);
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
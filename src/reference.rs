@@ -0,0 +1,108 @@
+//! A simple scalar implementation of pattern matching to compare the optimized
+//! one against, e.g. in differential tests of code built on this crate.
+//! It compares the whole pattern at every offset and has no anchors,
+//! prefilters or SIMD.
+//! ```
+//! use patterns::reference;
+//!
+//! let pattern = "01 ? 03";
+//! let data = [1, 2, 3, 0, 1, 1, 3];
+//! let found = patterns::Pattern::new(pattern)
+//!     .matches(&data)
+//!     .collect::<Vec<_>>();
+//! assert_eq!(found, reference::Pattern::new(pattern).matches(&data));
+//! ```
+
+use core::str::FromStr;
+use std::vec::Vec;
+
+use crate::ParsePatternError;
+
+/// A pattern as one optional byte per position, `None` is a wildcard.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Pattern(pub Vec<Option<u8>>);
+
+impl Pattern {
+    /// Parse a pattern in the syntax of [`crate::Pattern`], see the
+    /// [`FromStr`] impl.
+    /// # Panics
+    /// Panics if the pattern doesn't parse.
+    #[inline]
+    pub fn new(pattern: &str) -> Self {
+        pattern.parse().unwrap()
+    }
+
+    /// Returns `true` if the pattern matches at the start of data.
+    #[inline]
+    pub fn matches_at(&self, data: &[u8]) -> bool {
+        self.0.len() <= data.len()
+            && self
+                .0
+                .iter()
+                .zip(data)
+                .all(|(byte, data)| byte.is_none_or(|byte| byte == *data))
+    }
+
+    /// The offsets of all matches in data, in ascending order.
+    #[inline]
+    pub fn matches(&self, data: &[u8]) -> Vec<usize> {
+        (0..data.len())
+            .filter(|&offset| self.matches_at(&data[offset..]))
+            .collect()
+    }
+}
+
+/// Copies the bytes of a parsed pattern. The result inherits how
+/// [`crate::Pattern`] parsed them, so parse the text with [`Pattern::new`]
+/// to test the parser as well.
+impl From<&crate::Pattern> for Pattern {
+    #[inline]
+    fn from(pattern: &crate::Pattern) -> Self {
+        let mask = pattern.mask();
+        Self(
+            pattern
+                .bytes()
+                .iter()
+                .enumerate()
+                .map(|(index, &byte)| {
+                    (mask & (1 << (u64::BITS as usize - 1 - index)) != 0).then_some(byte)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = ParsePatternError;
+
+    /// Parses bytes separated by whitespace, each either one or two hex digits
+    /// or a wildcard made of `?` or `.`, e.g. `48 8b ?? 05`.
+    /// Independent of [`crate::Pattern`], there is no length limit and
+    /// trailing wildcards are kept.
+    /// ```
+    /// # use patterns::reference::Pattern;
+    /// assert_eq!(Pattern::new("01 ?? 3").0, [Some(1), None, Some(3)]);
+    /// assert_eq!(Pattern::new("01 ?").0, [Some(1), None]);
+    /// assert!("01 +2".parse::<Pattern>().is_err());
+    /// ```
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s
+            .split_ascii_whitespace()
+            .map(|token| {
+                if token.bytes().all(|char| char == b'?' || char == b'.') {
+                    return Ok(None);
+                }
+                let digits = token.len() <= 2 && token.bytes().all(|char| char.is_ascii_hexdigit());
+                // Anything else, e.g. a sign, fails with the error of an invalid
+                // digit.
+                let token = if digits { token } else { "x" };
+                Ok(Some(u8::from_str_radix(token, 16)?))
+            })
+            .collect::<Result<Vec<_>, ParsePatternError>>()?;
+        if bytes.iter().all(Option::is_none) {
+            return Err(ParsePatternError::MissingNonWildcardByte);
+        }
+        Ok(Self(bytes))
+    }
+}
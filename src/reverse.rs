@@ -0,0 +1,75 @@
+use core::iter::FusedIterator;
+
+use crate::{first_bits, Pattern, BYTES};
+
+impl Pattern {
+    /// Creates an iterator through data that searches backwards, starting
+    /// with the last match that starts before `offset`.
+    /// Matches may extend past `offset`.
+    /// ```
+    /// # use patterns::Pattern;
+    /// // Find the function prologue in front of an instruction.
+    /// let prologue = Pattern::new("55 48 8B EC");
+    /// let code = [
+    ///     0x55, 0x48, 0x8B, 0xEC, 0x90, 0x55, 0x48, 0x8B, 0xEC, 0x90, 0xC3,
+    /// ];
+    /// let mut found = prologue.rmatches_from(&code, 9);
+    /// assert_eq!(found.next(), Some(5));
+    /// assert_eq!(found.next(), Some(0));
+    /// assert_eq!(found.next(), None);
+    /// ```
+    #[inline]
+    pub fn rmatches_from<'pattern, 'data>(
+        &'pattern self,
+        data: &'data [u8],
+        offset: usize,
+    ) -> ReverseScanner<'pattern, 'data> {
+        // Matches have to fit into data.
+        let end = (data.len() + 1).saturating_sub(self.len()).min(offset);
+        ReverseScanner {
+            pattern: self,
+            data,
+            end,
+            base: 0,
+            pending: 0,
+        }
+    }
+}
+
+/// An iterator searching backwards through data, see
+/// [`Pattern::rmatches_from`]
+#[must_use]
+pub struct ReverseScanner<'pattern, 'data> {
+    pattern: &'pattern Pattern,
+    data: &'data [u8],
+    // Candidates at `end` and after have been checked already.
+    end: usize,
+    base: usize,
+    pending: u64,
+}
+
+impl<'pattern, 'data> Iterator for ReverseScanner<'pattern, 'data> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.pending != 0 {
+                let bit = (u64::BITS - 1 - self.pending.leading_zeros()) as usize;
+                self.pending ^= 1 << bit;
+                if self.pattern.verify(self.data, self.base + bit) {
+                    return Some(self.base + bit);
+                }
+            }
+            if self.end == 0 {
+                return None;
+            }
+            self.base = self.end.saturating_sub(BYTES);
+            self.pending =
+                self.pattern.candidates(self.data, self.base) & first_bits(self.end - self.base);
+            self.end = self.base;
+        }
+    }
+}
+
+impl<'pattern, 'data> FusedIterator for ReverseScanner<'pattern, 'data> {}
@@ -0,0 +1,257 @@
+use core::{error::Error, fmt};
+
+use crate::{MultiScanner, Pattern};
+
+/// Several named patterns and a condition over which of them occur, like a
+/// small subset of a YARA rule.
+///
+/// The condition supports `all of them`, `any of them`, `$name` for a single
+/// pattern, `not`, `and`, `or` and parentheses. `not` binds tighter than
+/// `and`, which binds tighter than `or`.
+/// ```
+/// # use patterns::{Pattern, Rule};
+/// let names = ["a", "b"];
+/// let patterns = [Pattern::new("01 02"), Pattern::new("03 04")];
+/// let rule = Rule::new(&names, &patterns, "$a and not $b").unwrap();
+/// assert!(rule.is_match(&[0, 1, 2, 0]));
+/// assert!(!rule.is_match(&[1, 2, 3, 4]));
+/// let rule = Rule::new(&names, &patterns, "any of them and not ($a and $b)").unwrap();
+/// assert!(rule.is_match(&[3, 4]));
+/// assert!(Rule::new(&names, &patterns, "$c").is_err());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Rule<'rule> {
+    names: &'rule [&'rule str],
+    patterns: &'rule [Pattern],
+    condition: &'rule str,
+    // Whether more matches can only turn the condition true, i.e. it has no
+    // `not`, so the search can stop as soon as it holds.
+    monotonic: bool,
+}
+
+impl<'rule> Rule<'rule> {
+    /// The most patterns a rule can hold.
+    pub const MAX_PATTERNS: usize = u64::BITS as usize;
+
+    /// Pairs every name with the pattern at the same index and checks the
+    /// condition. Names are referenced as `$name` in the condition.
+    /// # Errors
+    /// Returns an error if the condition can't be parsed or references a name
+    /// that isn't given, or if there are more than [`Rule::MAX_PATTERNS`]
+    /// patterns.
+    /// # Panics
+    /// Panics if `names` and `patterns` differ in length.
+    #[inline]
+    pub fn new(
+        names: &'rule [&'rule str],
+        patterns: &'rule [Pattern],
+        condition: &'rule str,
+    ) -> Result<Self, ParseRuleError> {
+        assert_eq!(names.len(), patterns.len(), "every pattern needs a name");
+        if patterns.len() > Self::MAX_PATTERNS {
+            return Err(ParseRuleError::TooManyPatterns);
+        }
+        let mut rule = Self {
+            names,
+            patterns,
+            condition,
+            monotonic: true,
+        };
+        let mut parser = Parser::new(&rule, 0);
+        parser.parse()?;
+        rule.monotonic = !parser.negated;
+        Ok(rule)
+    }
+
+    /// The number of patterns in the rule.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns `true` if the rule has no patterns.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Searches all patterns in a single pass through data and evaluates the
+    /// condition. Stops early once the result can't change anymore.
+    #[inline]
+    pub fn is_match(&self, data: &[u8]) -> bool {
+        let all = self.all();
+        let mut found = 0;
+        if self.monotonic && self.evaluate(found) {
+            return true;
+        }
+        for (pattern, _) in MultiScanner::new(self.patterns, data) {
+            let bit = 1 << pattern;
+            if found & bit != 0 {
+                continue;
+            }
+            found |= bit;
+            if found == all || self.monotonic && self.evaluate(found) {
+                break;
+            }
+        }
+        self.evaluate(found)
+    }
+
+    /// The bits of all patterns in the rule.
+    fn all(&self) -> u64 {
+        u64::MAX
+            .checked_shr((Self::MAX_PATTERNS - self.len()) as u32)
+            .unwrap_or(0)
+    }
+
+    /// Evaluates the condition, bit `n` of `found` is set if pattern `n`
+    /// occurs.
+    fn evaluate(&self, found: u64) -> bool {
+        // The condition was checked in `new`.
+        Parser::new(self, found).parse().unwrap_or(false)
+    }
+}
+
+/// Evaluates a condition while parsing it, so it needs no allocation.
+struct Parser<'rule> {
+    rule: &'rule Rule<'rule>,
+    found: u64,
+    rest: &'rule str,
+    // Whether the condition contains a `not`.
+    negated: bool,
+}
+
+impl<'rule> Parser<'rule> {
+    fn new(rule: &'rule Rule<'rule>, found: u64) -> Self {
+        Self {
+            rule,
+            found,
+            rest: rule.condition,
+            negated: false,
+        }
+    }
+
+    fn parse(&mut self) -> Result<bool, ParseRuleError> {
+        let value = self.or()?;
+        match self.next() {
+            None => Ok(value),
+            Some(_) => Err(ParseRuleError::UnexpectedToken),
+        }
+    }
+
+    fn or(&mut self) -> Result<bool, ParseRuleError> {
+        let mut value = self.and()?;
+        while self.eat("or") {
+            // No short circuit, the rest has to be parsed anyway.
+            value |= self.and()?;
+        }
+        Ok(value)
+    }
+
+    fn and(&mut self) -> Result<bool, ParseRuleError> {
+        let mut value = self.not()?;
+        while self.eat("and") {
+            value &= self.not()?;
+        }
+        Ok(value)
+    }
+
+    fn not(&mut self) -> Result<bool, ParseRuleError> {
+        if self.eat("not") {
+            self.negated = true;
+            return Ok(!self.not()?);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<bool, ParseRuleError> {
+        let token = self.next().ok_or(ParseRuleError::UnexpectedEnd)?;
+        match token {
+            "(" => {
+                let value = self.or()?;
+                if self.eat(")") {
+                    Ok(value)
+                } else {
+                    Err(ParseRuleError::UnexpectedToken)
+                }
+            }
+            "all" | "any" => {
+                if !(self.eat("of") && self.eat("them")) {
+                    return Err(ParseRuleError::UnexpectedToken);
+                }
+                let all = self.rule.all();
+                Ok(if token == "all" {
+                    self.found & all == all
+                } else {
+                    self.found & all != 0
+                })
+            }
+            _ => {
+                let name = token
+                    .strip_prefix('$')
+                    .ok_or(ParseRuleError::UnexpectedToken)?;
+                let index = self
+                    .rule
+                    .names
+                    .iter()
+                    .position(|candidate| *candidate == name)
+                    .ok_or(ParseRuleError::UnknownPattern)?;
+                Ok(self.found & 1 << index != 0)
+            }
+        }
+    }
+
+    /// Consumes the next token if it is `expected`.
+    fn eat(&mut self, expected: &str) -> bool {
+        let rest = self.rest;
+        if self.next() == Some(expected) {
+            true
+        } else {
+            self.rest = rest;
+            false
+        }
+    }
+
+    /// Splits off the next token, parentheses are tokens of their own.
+    fn next(&mut self) -> Option<&'rule str> {
+        let rest = self.rest.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+        let len = if rest.starts_with(['(', ')']) {
+            1
+        } else {
+            rest.find(|char: char| char.is_whitespace() || char == '(' || char == ')')
+                .unwrap_or(rest.len())
+        };
+        let (token, rest) = rest.split_at(len);
+        self.rest = rest;
+        Some(token)
+    }
+}
+
+/// The reasons a [`Rule`] can fail to parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseRuleError {
+    TooManyPatterns,
+    UnknownPattern,
+    UnexpectedToken,
+    UnexpectedEnd,
+}
+
+impl fmt::Display for ParseRuleError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyPatterns => {
+                write!(f, "rule has more than {} patterns", Rule::MAX_PATTERNS)
+            }
+            Self::UnknownPattern => f.write_str("condition references an unknown pattern"),
+            Self::UnexpectedToken => f.write_str("unexpected token in condition"),
+            Self::UnexpectedEnd => f.write_str("condition ends unexpectedly"),
+        }
+    }
+}
+
+impl Error for ParseRuleError {}
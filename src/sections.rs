@@ -0,0 +1,135 @@
+use object::{File, Object, ObjectSection, SectionIndex};
+
+use crate::{Pattern, Scanner};
+
+/// A match within a section of an executable file, see
+/// [`Pattern::matches_sections`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SectionMatch {
+    /// Where the match starts within the file
+    pub file_offset: u64,
+    /// Where the match is loaded in memory, relative to the image base the
+    /// file was linked for
+    pub address: u64,
+}
+
+impl Pattern {
+    /// Creates an iterator through the sections of an ELF, PE or Mach-O file
+    /// whose name `filter` accepts, e.g. only `.text`.
+    /// Sections without data in the file, like `.bss`, are skipped and matches
+    /// never span sections.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let file = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    /// let marker = "patterns-section-marker";
+    /// let pattern = Pattern::from_slice(marker.as_bytes(), u64::MAX);
+    /// // String literals are data, not code.
+    /// let in_code = pattern.matches_sections(&file, |name| name == ".text");
+    /// assert_eq!(in_code.unwrap().count(), 0);
+    /// let anywhere = pattern.matches_sections(&file, |_| true);
+    /// assert!(anywhere.unwrap().count() > 0);
+    /// ```
+    /// # Errors
+    /// Returns an error if file isn't an executable file of a known format.
+    #[inline]
+    pub fn matches_sections<'pattern, 'data, F>(
+        &'pattern self,
+        file: &'data [u8],
+        filter: F,
+    ) -> object::Result<SectionMatches<'pattern, 'data, F>>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let object = File::parse(file)?;
+        let sections = object
+            .sections()
+            .map(|section| section.index().0 + 1)
+            .max()
+            .unwrap_or(0);
+        Ok(SectionMatches {
+            pattern: self,
+            file,
+            object,
+            filter,
+            next: 0,
+            sections,
+            file_offset: 0,
+            address: 0,
+            scanner: self.matches(&[]),
+        })
+    }
+}
+
+/// An iterator through the sections of an executable file, see
+/// [`Pattern::matches_sections`]
+#[must_use]
+pub struct SectionMatches<'pattern, 'data, F> {
+    pattern: &'pattern Pattern,
+    file: &'data [u8],
+    object: File<'data>,
+    filter: F,
+    // The index of the next section and the end of the indices.
+    next: usize,
+    sections: usize,
+    // Where the section the scanner runs through starts.
+    file_offset: u64,
+    address: u64,
+    scanner: Scanner<'pattern, 'data, 'data>,
+}
+
+impl<'pattern, 'data, F> SectionMatches<'pattern, 'data, F>
+where
+    F: FnMut(&str) -> bool,
+{
+    /// Points the scanner at the next accepted section with data, returns
+    /// `false` after the last one.
+    fn open_section(&mut self) -> bool {
+        while self.next < self.sections {
+            let index = SectionIndex(self.next);
+            self.next += 1;
+            let Ok(section) = self.object.section_by_index(index) else {
+                continue;
+            };
+            if !section.name().is_ok_and(&mut self.filter) {
+                continue;
+            }
+            let Some((offset, size)) = section.file_range() else {
+                continue;
+            };
+            let Some(data) = usize::try_from(offset)
+                .ok()
+                .zip(usize::try_from(size).ok())
+                .and_then(|(offset, size)| self.file.get(offset..offset.checked_add(size)?))
+            else {
+                continue;
+            };
+            self.file_offset = offset;
+            self.address = section.address();
+            self.scanner = self.pattern.matches(data);
+            return true;
+        }
+        false
+    }
+}
+
+impl<'pattern, 'data, F> Iterator for SectionMatches<'pattern, 'data, F>
+where
+    F: FnMut(&str) -> bool,
+{
+    type Item = SectionMatch;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(index) = self.scanner.next() {
+                return Some(SectionMatch {
+                    file_offset: self.file_offset + index as u64,
+                    address: self.address + index as u64,
+                });
+            }
+            if !self.open_section() {
+                return None;
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use core::{error::Error, fmt, iter};
+
+use crate::{ParsePatternError, Pattern, Uniqueness, BYTES};
+
+impl Pattern {
+    /// Creates the shortest pattern that contains `target` in data and
+    /// matches nowhere else, e.g. to find a function again after an update.
+    /// The pattern grows forward from the target first. If that isn't unique,
+    /// e.g. at the end of a function, it starts at earlier bytes instead.
+    /// Returns the pattern and how far the target lies behind its start.
+    ///
+    /// `variants` are other copies of data with the same layout, e.g. the
+    /// binary loaded at another base address so that relocations differ.
+    /// Bytes that differ from data in any variant become wildcards and the
+    /// pattern has to be unique in every variant as well.
+    /// Returns `None` if no pattern of up to [`BYTES`] bytes is unique.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let data = [0xe8, 1, 0, 0, 0, 0xe8, 2, 0, 0, 0, 0xc3];
+    /// let relocated = [0xe8, 9, 0, 0, 0, 0xe8, 8, 0, 0, 0, 0xc3];
+    /// let (signature, distance) = Pattern::make_signature(&data, 5, &[&relocated]).unwrap();
+    /// assert_eq!(signature.to_string(), "e8 ? 00 00 00 c3");
+    /// assert_eq!(distance, 0);
+    /// assert_eq!(signature.matches(&data).collect::<Vec<_>>(), [5]);
+    ///
+    /// // Only the bytes before the second `nop` tell it apart.
+    /// let data = [0x55, 0x48, 0x90, 0xc3, 0x53, 0x48, 0x90, 0xc3];
+    /// let (signature, distance) = Pattern::make_signature(&data, 6, &[]).unwrap();
+    /// assert_eq!(signature.to_string(), "53 48 90");
+    /// assert_eq!(distance, 2);
+    /// ```
+    #[inline]
+    pub fn make_signature(data: &[u8], target: usize, variants: &[&[u8]]) -> Option<(Self, usize)> {
+        if target >= data.len() {
+            return None;
+        }
+        let signature = |start: usize, len: usize| {
+            let mut bytes = [0; BYTES];
+            let mut mask = 0;
+            for index in 0..len {
+                let byte = data[start + index];
+                let stable = variants
+                    .iter()
+                    .all(|variant| variant.get(start + index) == Some(&byte));
+                if stable {
+                    bytes[index] = byte;
+                    mask |= 1 << (u64::BITS as usize - 1 - index);
+                }
+            }
+            Self::try_from_slice(&bytes[..len], mask).ok()
+        };
+        let unique = |pattern: &Self| {
+            iter::once(data)
+                .chain(variants.iter().copied())
+                .all(|data| pattern.uniqueness(&[data]).is_unique())
+        };
+        for distance in 0..BYTES.min(target + 1) {
+            let start = target - distance;
+            let available = (data.len() - start).min(BYTES);
+            // Longer patterns only match less, so no pattern from this start
+            // is unique if the longest isn't.
+            if !signature(start, available).is_some_and(|pattern| unique(&pattern)) {
+                continue;
+            }
+            for len in distance + 1..=available {
+                if let Some(pattern) = signature(start, len).filter(|pattern| unique(pattern)) {
+                    return Some((pattern, distance));
+                }
+            }
+        }
+        None
+    }
+
+    /// Creates a pattern from two versions of the same bytes, e.g. a function
+    /// before and after an update, with wildcards where they differ.
+    /// Only the length of the shorter version is used. Check the result with
+    /// [`Pattern::uniqueness`] in both binaries, or use
+    /// [`Pattern::from_diff_unique`].
+    /// ```
+    /// # use patterns::Pattern;
+    /// let old = [0x48, 0x8b, 0x05, 0x10, 0x20, 0xc3];
+    /// let new = [0x48, 0x8b, 0x05, 0x30, 0x20, 0xc3, 0xcc];
+    /// let pattern = Pattern::from_diff(&old, &new).unwrap();
+    /// assert_eq!(pattern.to_string(), "48 8b 05 ? 20 c3");
+    ///
+    /// let old_binary = [&[0_u8, 0][..], &old, &[0xc3]].concat();
+    /// let new_binary = [&new[..], &[0x48, 0x8b]].concat();
+    /// assert!(pattern.uniqueness(&[&old_binary]).is_unique());
+    /// assert!(pattern.uniqueness(&[&new_binary]).is_unique());
+    /// ```
+    /// # Errors
+    /// Returns an error if the shorter version is longer than [`BYTES`] or the
+    /// versions differ in every byte.
+    #[inline]
+    pub fn from_diff(old: &[u8], new: &[u8]) -> Result<Self, ParsePatternError> {
+        let len = old.len().min(new.len());
+        if len > BYTES {
+            return Err(ParsePatternError::PatternTooLong);
+        }
+        let mask = old
+            .iter()
+            .zip(new)
+            .enumerate()
+            .filter(|(_, (old, new))| old == new)
+            .fold(0, |mask, (index, _)| {
+                mask | 1 << (u64::BITS as usize - 1 - index)
+            });
+        Self::try_from_slice(&old[..len], mask)
+    }
+
+    /// Creates a pattern like [`Pattern::from_diff`] and checks that it
+    /// matches exactly once in every one of `binaries`, e.g. the old and the
+    /// new binary.
+    /// ```
+    /// # use patterns::{DiffError, Pattern, Uniqueness};
+    /// let old = [0x48, 0x8b, 0x05, 0x10, 0x20, 0xc3];
+    /// let new = [0x48, 0x8b, 0x05, 0x30, 0x20, 0xc3];
+    /// let old_binary = [&[0_u8, 0][..], &old].concat();
+    /// let new_binary = [&new[..], &[0x48, 0x8b]].concat();
+    /// let pattern = Pattern::from_diff_unique(&old, &new, &[&old_binary, &new_binary]);
+    /// assert_eq!(pattern.unwrap().to_string(), "48 8b 05 ? 20 c3");
+    ///
+    /// let twice = [&new[..], &new].concat();
+    /// let error = Pattern::from_diff_unique(&old, &new, &[&old_binary, &twice]).unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     DiffError::NotUnique {
+    ///         binary: 1,
+    ///         uniqueness: Uniqueness::Ambiguous { count: 2, .. },
+    ///     }
+    /// ));
+    /// ```
+    /// # Errors
+    /// Returns an error if [`Pattern::from_diff`] does, or for the first
+    /// binary the pattern doesn't match exactly once in.
+    #[inline]
+    pub fn from_diff_unique(old: &[u8], new: &[u8], binaries: &[&[u8]]) -> Result<Self, DiffError> {
+        let pattern = Self::from_diff(old, new)?;
+        for (binary, data) in binaries.iter().enumerate() {
+            let uniqueness = pattern.uniqueness(&[data]);
+            if !uniqueness.is_unique() {
+                return Err(DiffError::NotUnique { binary, uniqueness });
+            }
+        }
+        Ok(pattern)
+    }
+}
+
+/// The reasons [`Pattern::from_diff_unique`] can fail.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DiffError {
+    /// The versions don't make a pattern.
+    Pattern(ParsePatternError),
+    /// The pattern doesn't match exactly once in the binary at this index.
+    NotUnique {
+        binary: usize,
+        uniqueness: Uniqueness,
+    },
+}
+
+impl fmt::Display for DiffError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pattern(error) => error.fmt(f),
+            Self::NotUnique {
+                binary,
+                uniqueness: Uniqueness::Missing,
+            } => write!(f, "pattern is missing in binary {binary}"),
+            Self::NotUnique { binary, .. } => {
+                write!(f, "pattern matches more than once in binary {binary}")
+            }
+        }
+    }
+}
+
+impl Error for DiffError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Pattern(error) => Some(error),
+            Self::NotUnique { .. } => None,
+        }
+    }
+}
+
+impl From<ParsePatternError> for DiffError {
+    #[inline]
+    fn from(value: ParsePatternError) -> Self {
+        Self::Pattern(value)
+    }
+}
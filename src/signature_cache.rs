@@ -0,0 +1,167 @@
+use core::{error::Error, fmt, str::FromStr, time::Duration};
+use std::{
+    collections::BTreeMap,
+    fs::Metadata,
+    string::{String, ToString},
+    time::UNIX_EPOCH,
+};
+
+use crate::Pattern;
+
+/// Remembers where patterns matched in a module, so that a later run only
+/// has to check the remembered offset instead of scanning again.
+///
+/// Offsets are stored per module identity and pattern text. An offset is
+/// revalidated with [`Pattern::matches_at`] before it is returned, the
+/// module is scanned again only if it doesn't match anymore, e.g. after an
+/// update that kept the identity.
+///
+/// [`Display`](fmt::Display) writes one entry per line, as the identity in
+/// hex, the offset and the pattern, which [`FromStr`] reads back.
+/// ```
+/// # use patterns::{Pattern, SignatureCache};
+/// let pattern = Pattern::new("e8 ? ? 90");
+/// let data = [0, 0xe8, 1, 2, 0x90];
+/// let mut cache = SignatureCache::new();
+/// assert_eq!(cache.find(7, &pattern, &data), Some(1));
+/// let saved = cache.to_string();
+/// assert_eq!(saved, "0000000000000007 1 e8 ? ? 90\n");
+///
+/// // The next run only checks the offset.
+/// let mut cache: SignatureCache = saved.parse().unwrap();
+/// assert_eq!(cache.find(7, &pattern, &data), Some(1));
+/// // Falls back to a scan if the code moved.
+/// let moved = [0, 0, 0xe8, 3, 4, 0x90];
+/// assert_eq!(cache.find(7, &pattern, &moved), Some(2));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SignatureCache {
+    offsets: BTreeMap<(u64, String), usize>,
+}
+
+impl SignatureCache {
+    /// Creates an empty cache.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            offsets: BTreeMap::new(),
+        }
+    }
+
+    /// An identity for a module file from its size and modification time,
+    /// which changes with nearly every update. It stays the same across runs
+    /// and Rust versions. Any other number works as well, e.g. a hash of the
+    /// headers or the build id.
+    #[inline]
+    pub fn identity(metadata: &Metadata) -> u64 {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or(Duration::ZERO);
+        // FNV-1a
+        [
+            metadata.len(),
+            modified.as_secs(),
+            modified.subsec_nanos().into(),
+        ]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    /// Returns the first match of pattern in the module data, from the cache
+    /// if the remembered offset still matches and by a scan otherwise, whose
+    /// result is remembered. Modules without a match are scanned every time.
+    #[inline]
+    pub fn find(&mut self, module: u64, pattern: &Pattern, data: &[u8]) -> Option<usize> {
+        let key = (module, pattern.to_string());
+        if let Some(&offset) = self.offsets.get(&key) {
+            if pattern.matches_at(data, offset) {
+                return Some(offset);
+            }
+        }
+        let found = pattern.find(data);
+        match found {
+            Some(offset) => self.offsets.insert(key, offset),
+            None => self.offsets.remove(&key),
+        };
+        found
+    }
+
+    /// The number of remembered offsets.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns `true` if no offsets are remembered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Forgets the offsets of all modules but the ones `keep` accepts, e.g.
+    /// to drop the ones of older versions before saving.
+    #[inline]
+    pub fn retain_modules<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(u64) -> bool,
+    {
+        self.offsets.retain(|&(module, _), _| keep(module));
+    }
+}
+
+impl FromStr for SignatureCache {
+    type Err = ParseSignatureCacheError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cache = Self::new();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let entry = || {
+                let (module, rest) = line.split_once(' ')?;
+                let (offset, pattern) = rest.split_once(' ')?;
+                let module = u64::from_str_radix(module, 16).ok()?;
+                // Normalized the way `find` writes the key.
+                let pattern = pattern.parse::<Pattern>().ok()?.to_string();
+                Some(((module, pattern), offset.parse().ok()?))
+            };
+            let (key, offset) = entry().ok_or(ParseSignatureCacheError { line: index + 1 })?;
+            cache.offsets.insert(key, offset);
+        }
+        Ok(cache)
+    }
+}
+
+impl fmt::Display for SignatureCache {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for ((module, pattern), offset) in &self.offsets {
+            writeln!(f, "{module:016x} {offset} {pattern}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The line a [`SignatureCache`] failed to parse at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseSignatureCacheError {
+    /// The line, starting at 1
+    pub line: usize,
+}
+
+impl fmt::Display for ParseSignatureCacheError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: expected `identity offset pattern`", self.line)
+    }
+}
+
+impl Error for ParseSignatureCacheError {}
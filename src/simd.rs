@@ -0,0 +1,126 @@
+#[cfg(not(feature = "stable"))]
+pub(crate) use core::simd::{cmp::SimdPartialEq, Mask, Simd};
+
+#[cfg(feature = "stable")]
+pub(crate) use self::fallback::{Mask, Simd, SimdPartialEq};
+
+/// The parts of `core::simd` this crate uses, on plain arrays.
+/// Builds on stable Rust. LLVM vectorizes most of the loops, the rest of the
+/// crate works unchanged.
+#[cfg(feature = "stable")]
+mod fallback {
+    use core::{
+        marker::PhantomData,
+        ops::{BitAnd, Index},
+    };
+
+    use crate::BYTES;
+
+    /// Aligned like the vector type, to keep the layout of `Pattern`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[repr(C, align(64))]
+    pub(crate) struct Simd<T, const N: usize>([T; N]);
+
+    impl Simd<u8, BYTES> {
+        #[inline(always)]
+        pub(crate) const fn from_array(array: [u8; BYTES]) -> Self {
+            Self(array)
+        }
+
+        #[inline(always)]
+        pub(crate) const fn splat(value: u8) -> Self {
+            Self([value; BYTES])
+        }
+
+        #[inline(always)]
+        pub(crate) const fn as_array(&self) -> &[u8; BYTES] {
+            &self.0
+        }
+
+        /// # Panics
+        /// Panics if slice is shorter than `BYTES`.
+        #[inline(always)]
+        pub(crate) fn from_slice(slice: &[u8]) -> Self {
+            let mut array = [0; BYTES];
+            array.copy_from_slice(&slice[..BYTES]);
+            Self(array)
+        }
+
+        #[inline(always)]
+        pub(crate) fn load_or_default(slice: &[u8]) -> Self {
+            let mut array = [0; BYTES];
+            let len = slice.len().min(BYTES);
+            array[..len].copy_from_slice(&slice[..len]);
+            Self(array)
+        }
+    }
+
+    impl<T, const N: usize> Index<usize> for Simd<T, N> {
+        type Output = T;
+
+        #[inline(always)]
+        fn index(&self, index: usize) -> &T {
+            &self.0[index]
+        }
+    }
+
+    pub(crate) trait SimdPartialEq {
+        type Mask;
+
+        fn simd_eq(self, other: Self) -> Self::Mask;
+    }
+
+    impl SimdPartialEq for Simd<u8, BYTES> {
+        type Mask = Mask<i8, BYTES>;
+
+        #[inline(always)]
+        fn simd_eq(self, other: Self) -> Self::Mask {
+            let mut bits = 0;
+            for (index, (a, b)) in self.0.iter().zip(other.0).enumerate() {
+                bits |= u64::from(*a == b) << index;
+            }
+            Mask::from_bitmask(bits)
+        }
+    }
+
+    /// One bit per lane, aligned like the vector type to keep the layout of
+    /// `Pattern`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[repr(C, align(64))]
+    pub(crate) struct Mask<T, const N: usize>(u64, PhantomData<T>);
+
+    impl Mask<i8, BYTES> {
+        #[inline(always)]
+        pub(crate) const fn from_bitmask(bits: u64) -> Self {
+            Self(bits, PhantomData)
+        }
+
+        #[inline(always)]
+        pub(crate) const fn to_bitmask(self) -> u64 {
+            self.0
+        }
+
+        #[inline(always)]
+        pub(crate) fn from_array(array: [bool; BYTES]) -> Self {
+            let mut bits = 0;
+            for (index, set) in array.into_iter().enumerate() {
+                bits |= u64::from(set) << index;
+            }
+            Self::from_bitmask(bits)
+        }
+
+        #[inline(always)]
+        pub(crate) const fn any(self) -> bool {
+            self.0 != 0
+        }
+    }
+
+    impl BitAnd for Mask<i8, BYTES> {
+        type Output = Self;
+
+        #[inline(always)]
+        fn bitand(self, other: Self) -> Self {
+            Self::from_bitmask(self.0 & other.0)
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use core::ops::Range;
+
+use crate::Scanner;
+
+/// A match of a pattern in data
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Match {
+    /// Where the match starts within data
+    pub offset: usize,
+    /// The number of bytes the match spans, up to the last non-wildcard byte
+    pub len: usize,
+}
+
+impl Match {
+    /// The range of data the match covers.
+    #[inline]
+    pub const fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+
+    /// The matched bytes, cut short at the end of data.
+    #[inline]
+    pub fn bytes<'data>(&self, data: &'data [u8]) -> &'data [u8] {
+        self.context(data, 0, 0)
+    }
+
+    /// The matched bytes together with up to `before` bytes in front of them
+    /// and up to `after` bytes behind them. Clamped to the edges of data.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 02");
+    /// let data = [7, 8, 1, 2, 9];
+    /// let found = pattern.matches(&data).spans().next().unwrap();
+    /// assert_eq!(found.context(&data, 1, 5), [8, 1, 2, 9]);
+    /// ```
+    #[inline]
+    pub fn context<'data>(&self, data: &'data [u8], before: usize, after: usize) -> &'data [u8] {
+        let end = (self.offset + self.len)
+            .saturating_add(after)
+            .min(data.len());
+        let start = self.offset.saturating_sub(before).min(end);
+        &data[start..end]
+    }
+}
+
+impl<'pattern, 'data: 'cursor, 'cursor> Scanner<'pattern, 'data, 'cursor> {
+    /// Yields [`Match`]es instead of offsets.
+    #[inline]
+    pub fn spans(self) -> Spans<'pattern, 'data, 'cursor> {
+        Spans {
+            len: self.pattern.len(),
+            scanner: self,
+        }
+    }
+
+    /// Yields the ranges covered by matches, merging matches that overlap or
+    /// touch into a single range.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 01");
+    /// let data = [1, 1, 1, 0, 1, 1, 1, 1, 0];
+    /// let found = pattern.matches(&data).merged().collect::<Vec<_>>();
+    /// assert_eq!(found, [0..3, 4..8]);
+    /// ```
+    #[inline]
+    pub fn merged(self) -> Merged<'pattern, 'data, 'cursor> {
+        Merged {
+            spans: self.spans(),
+            pending: None,
+        }
+    }
+}
+
+/// An iterator yielding [`Match`]es, see [`Scanner::spans`]
+#[must_use]
+pub struct Spans<'pattern, 'data: 'cursor, 'cursor> {
+    scanner: Scanner<'pattern, 'data, 'cursor>,
+    len: usize,
+}
+
+impl<'pattern, 'data: 'cursor, 'cursor> Iterator for Spans<'pattern, 'data, 'cursor> {
+    type Item = Match;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.scanner.next().map(|offset| Match {
+            offset,
+            len: self.len,
+        })
+    }
+}
+
+/// An iterator yielding merged ranges of matches, see [`Scanner::merged`]
+#[must_use]
+pub struct Merged<'pattern, 'data: 'cursor, 'cursor> {
+    spans: Spans<'pattern, 'data, 'cursor>,
+    // The range that is still growing.
+    pending: Option<Range<usize>>,
+}
+
+impl<'pattern, 'data: 'cursor, 'cursor> Iterator for Merged<'pattern, 'data, 'cursor> {
+    type Item = Range<usize>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        for span in self.spans.by_ref() {
+            let range = span.range();
+            match &mut self.pending {
+                Some(pending) if range.start <= pending.end => pending.end = range.end,
+                pending => {
+                    if let Some(done) = pending.replace(range) {
+                        return Some(done);
+                    }
+                }
+            }
+        }
+        self.pending.take()
+    }
+}
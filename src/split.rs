@@ -0,0 +1,75 @@
+use core::{iter::FusedIterator, ops::Range};
+
+use crate::Pattern;
+
+impl Pattern {
+    /// Splits data into `count` ranges that can be scanned independently, e.g.
+    /// on different threads.
+    /// Consecutive ranges overlap by `pattern.len() - 1` bytes. Scanning every
+    /// range on its own and adding its start to the results finds every match
+    /// exactly once.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 02");
+    /// let data = [0, 1, 2, 1, 2, 0, 0];
+    /// let mut found = Vec::new();
+    /// for range in pattern.split_points(&data, 3) {
+    ///     let start = range.start;
+    ///     found.extend(pattern.matches(&data[range]).map(|index| start + index));
+    /// }
+    /// assert_eq!(found, [1, 3]);
+    /// ```
+    #[inline]
+    pub fn split_points(&self, data: &[u8], count: usize) -> SplitPoints {
+        let size = data.len().div_ceil(count.max(1)).max(1);
+        SplitPoints {
+            size,
+            overlap: self.len() - 1,
+            len: data.len(),
+            next: 0,
+            count: data.len().div_ceil(size),
+        }
+    }
+}
+
+/// An iterator over the ranges of [`Pattern::split_points`]
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct SplitPoints {
+    size: usize,
+    overlap: usize,
+    len: usize,
+    next: usize,
+    count: usize,
+}
+
+impl SplitPoints {
+    /// The range of the chunk at `index`.
+    pub(crate) fn range(&self, index: usize) -> Range<usize> {
+        let start = index * self.size;
+        start..(start + self.size + self.overlap).min(self.len)
+    }
+}
+
+impl Iterator for SplitPoints {
+    type Item = Range<usize>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.count {
+            return None;
+        }
+        self.next += 1;
+        Some(self.range(self.next - 1))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for SplitPoints {}
+
+impl FusedIterator for SplitPoints {}
@@ -0,0 +1,99 @@
+//! [`proptest`] strategies for patterns and the data they are searched in.
+//! ```
+//! use patterns::strategy;
+//! use proptest::{prelude::*, test_runner::TestRunner};
+//!
+//! let found = strategy::pattern(1..=16, 0.25).prop_flat_map(|pattern| {
+//!     let data = strategy::data_with_match(&pattern, 0..256);
+//!     (Just(pattern), data)
+//! });
+//! TestRunner::default()
+//!     .run(&found, |(pattern, (data, offset))| {
+//!         prop_assert!(pattern.matches(&data).any(|found| found == offset));
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+use core::ops::{Range, RangeInclusive};
+use std::vec::Vec;
+
+use proptest::{collection, prelude::*};
+
+use crate::{Pattern, BYTES};
+
+/// Generates patterns with a length in `len`, limited to 1 to [`BYTES`]
+/// bytes. Every byte is a wildcard with a probability of `wildcards`, at least
+/// one byte never is.
+/// # Panics
+/// Panics if `wildcards` isn't between 0 and 1.
+#[inline]
+pub fn pattern(len: RangeInclusive<usize>, wildcards: f64) -> impl Strategy<Value = Pattern> {
+    let len = (*len.start()).clamp(1, BYTES)..=(*len.end()).clamp(1, BYTES);
+    len.prop_flat_map(move |len| {
+        (
+            collection::vec(any::<u8>(), len),
+            collection::vec(proptest::bool::weighted(wildcards), len),
+        )
+    })
+    .prop_map(|(bytes, wildcard)| {
+        let mut mask = wildcard
+            .iter()
+            .enumerate()
+            .filter(|&(_, &wildcard)| !wildcard)
+            .fold(0, |mask, (index, _)| {
+                mask | 1 << (u64::BITS as usize - 1 - index)
+            });
+        if mask == 0 {
+            // The last byte keeps the whole length.
+            mask = 1 << (u64::BITS as usize - bytes.len());
+        }
+        Pattern::from_slice(&bytes, mask)
+    })
+}
+
+/// Generates data with a length in `len` that contains a match of pattern,
+/// together with the offset of that match. Data is at least as long as the
+/// pattern and can contain more matches.
+#[inline]
+pub fn data_with_match(
+    pattern: &Pattern,
+    len: Range<usize>,
+) -> impl Strategy<Value = (Vec<u8>, usize)> {
+    let pattern = pattern.clone();
+    let min = pattern.len();
+    let len = len.start.max(min)..len.end.max(min + 1);
+    collection::vec(any::<u8>(), len)
+        .prop_flat_map(move |data| {
+            let offsets = 0..=data.len() - min;
+            (Just(data), offsets)
+        })
+        .prop_map(move |(mut data, offset)| {
+            let mask = pattern.mask();
+            for (index, &byte) in pattern.bytes().iter().enumerate() {
+                if mask & (1 << (u64::BITS as usize - 1 - index)) != 0 {
+                    data[offset + index] = byte;
+                }
+            }
+            (data, offset)
+        })
+}
+
+/// Generates data with a length in `len` that doesn't contain any match of
+/// pattern.
+#[inline]
+pub fn data_without_match(pattern: &Pattern, len: Range<usize>) -> impl Strategy<Value = Vec<u8>> {
+    let pattern = pattern.clone();
+    collection::vec(any::<u8>(), len).prop_map(move |mut data| {
+        // Overwriting the anchor of a match with a byte the pattern doesn't
+        // contain can't create another match.
+        let mut unused = 0_u8;
+        while pattern.bytes().contains(&unused) {
+            unused += 1;
+        }
+        while let Some(index) = pattern.find(&data) {
+            data[index + pattern.first_offset] = unused;
+        }
+        data
+    })
+}
@@ -0,0 +1,178 @@
+use crate::{first_bits, Pattern, Scanner, BYTES};
+
+/// Searches a pattern in data that arrives in chunks, e.g. from a file or a
+/// socket.
+/// Matches spanning chunk boundaries are found by carrying the last
+/// bytes of every chunk over to the next one. Offsets count from the start of
+/// the stream.
+/// ```
+/// # use patterns::{Pattern, StreamScanner};
+/// let pattern = Pattern::new("01 02 03");
+/// let mut stream = StreamScanner::new(&pattern);
+/// assert_eq!(stream.feed(&[0, 1]).collect::<Vec<_>>(), []);
+/// assert_eq!(stream.feed(&[2, 3, 1, 2, 3]).collect::<Vec<_>>(), [1, 4]);
+/// ```
+#[must_use]
+pub struct StreamScanner<'pattern> {
+    pattern: &'pattern Pattern,
+    // The last `pattern.len() - 1` bytes of the stream, at most.
+    carry: [u8; BYTES],
+    carry_len: usize,
+    position: u64,
+}
+
+impl<'pattern> StreamScanner<'pattern> {
+    /// Create a scanner for an empty stream
+    #[inline]
+    pub const fn new(pattern: &'pattern Pattern) -> Self {
+        Self {
+            pattern,
+            carry: [0; BYTES],
+            carry_len: 0,
+            position: 0,
+        }
+    }
+
+    /// The number of bytes fed into the stream so far.
+    #[inline]
+    pub const fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Skips `len` bytes of the stream that can't be read, e.g. a guard page or
+    /// a decommitted region of a process.
+    /// Offsets after the gap still count the skipped bytes, and no match can
+    /// span the gap.
+    /// ```
+    /// # use patterns::{Pattern, StreamScanner};
+    /// let pattern = Pattern::new("01 02");
+    /// let mut stream = StreamScanner::new(&pattern);
+    /// assert_eq!(stream.feed(&[0, 1]).count(), 0);
+    /// stream.skip(4096);
+    /// assert_eq!(stream.feed(&[2, 1, 2]).collect::<Vec<_>>(), [4099]);
+    /// ```
+    #[inline]
+    pub fn skip(&mut self, len: u64) {
+        self.position += len;
+        self.carry_len = 0;
+    }
+
+    /// Appends a chunk to the stream and returns an iterator over all matches
+    /// that end within this chunk.
+    #[inline]
+    pub fn feed<'data>(&mut self, chunk: &'data [u8]) -> StreamMatches<'pattern, 'data> {
+        let keep = self.pattern.len() - 1;
+
+        // Stitch the carried over bytes together with the start of this chunk to
+        // look for matches across the seam.
+        let mut window = [0_u8; 2 * BYTES];
+        let head = chunk.len().min(keep);
+        window[..self.carry_len].copy_from_slice(&self.carry[..self.carry_len]);
+        window[self.carry_len..self.carry_len + head].copy_from_slice(&chunk[..head]);
+        let window = &window[..self.carry_len + head];
+
+        // Matches starting in the chunk itself are found by the scanner below.
+        let starts = (window.len() + 1)
+            .saturating_sub(self.pattern.len())
+            .min(self.carry_len);
+        let candidates = self.pattern.candidates(window, 0) & first_bits(starts);
+        let seam = self.pattern.verified(window, 0, candidates);
+        let seam_base = self.position - self.carry_len as u64;
+
+        // If the chunk is too short, the window holds the whole tail of the stream.
+        let tail = if chunk.len() >= keep {
+            &chunk[chunk.len() - keep..]
+        } else {
+            &window[window.len().saturating_sub(keep)..]
+        };
+        self.carry[..tail.len()].copy_from_slice(tail);
+        self.carry_len = tail.len();
+
+        let chunk_base = self.position;
+        self.position += chunk.len() as u64;
+
+        StreamMatches {
+            seam,
+            seam_base,
+            scanner: self.pattern.matches(chunk),
+            chunk_base,
+        }
+    }
+}
+
+impl Pattern {
+    /// Creates an iterator through several slices of data as if they were one
+    /// continuous slice, e.g. memory pages or io vectors.
+    /// Yields offsets into the concatenation of all slices, including matches
+    /// that span several slices.
+    /// ```
+    /// # use patterns::Pattern;
+    /// let pattern = Pattern::new("01 02");
+    /// let pages: [&[u8]; 3] = [&[1, 2, 1], &[], &[2, 0]];
+    /// assert_eq!(pattern.matches_segments(pages).collect::<Vec<_>>(), [0, 2]);
+    /// ```
+    #[inline]
+    pub fn matches_segments<'data, I>(&self, segments: I) -> SegmentMatches<'_, 'data, I::IntoIter>
+    where
+        I: IntoIterator<Item = &'data [u8]>,
+    {
+        SegmentMatches {
+            stream: StreamScanner::new(self),
+            segments: segments.into_iter(),
+            current: None,
+        }
+    }
+}
+
+/// An iterator through several slices of data, see
+/// [`Pattern::matches_segments`]
+#[must_use]
+pub struct SegmentMatches<'pattern, 'data, I> {
+    stream: StreamScanner<'pattern>,
+    segments: I,
+    current: Option<StreamMatches<'pattern, 'data>>,
+}
+
+impl<'pattern, 'data, I> Iterator for SegmentMatches<'pattern, 'data, I>
+where
+    I: Iterator<Item = &'data [u8]>,
+{
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(index) = self.current.as_mut().and_then(Iterator::next) {
+                // All segments are in memory at the same time, so this can't overflow.
+                return Some(index as usize);
+            }
+            let segment = self.segments.next()?;
+            self.current = Some(self.stream.feed(segment));
+        }
+    }
+}
+
+/// The matches found in a single chunk, see [`StreamScanner::feed`]
+#[must_use]
+pub struct StreamMatches<'pattern, 'data> {
+    seam: u64,
+    seam_base: u64,
+    scanner: Scanner<'pattern, 'data, 'data>,
+    chunk_base: u64,
+}
+
+impl<'pattern, 'data> Iterator for StreamMatches<'pattern, 'data> {
+    type Item = u64;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.seam != 0 {
+            let index = self.seam.trailing_zeros();
+            self.seam &= self.seam - 1;
+            return Some(self.seam_base + u64::from(index));
+        }
+        self.scanner
+            .next()
+            .map(|index| self.chunk_base + index as u64)
+    }
+}
@@ -0,0 +1,109 @@
+use core::ops::ControlFlow;
+
+use crate::{Adaptive, Pattern, Prefilter, BYTES};
+
+/// Bytes per word.
+const WORD: usize = 8;
+const LOW_BITS: u64 = u64::from_ne_bytes([0x7f; WORD]);
+
+/// Whether the target has vector registers `core::simd` can lower to.
+/// Without them, every `Simd` operation is emulated lane by lane.
+pub(crate) const HAS_SIMD: bool = cfg!(any(
+    target_feature = "sse2",
+    target_feature = "neon",
+    target_feature = "simd128",
+    target_feature = "altivec",
+    target_feature = "msa",
+    target_feature = "v",
+));
+
+/// The same search as [`crate::fold_chunks`], looking at one 64 bit word
+/// at a time and verifying candidates byte by byte.
+pub(crate) fn fold_chunks<B, T>(
+    pattern: &Pattern,
+    haystack: &[u8],
+    cursor: &mut usize,
+    adaptive: &mut Adaptive,
+    init: B,
+    mut f: impl FnMut(B, usize, u64) -> ControlFlow<T, B>,
+) -> ControlFlow<T, B> {
+    let mut acc = init;
+    let first_byte = pattern.first_byte[0];
+    loop {
+        if haystack.len() - *cursor < BYTES + pattern.reach(adaptive.prefilter) {
+            break ControlFlow::Continue(acc);
+        }
+        let window = &haystack[*cursor..];
+
+        let mut candidates = equal_bytes(word(window, pattern.first_offset), first_byte);
+        if adaptive.prefilter == Prefilter::TwoBytes {
+            candidates &= equal_bytes(word(window, pattern.second_offset), pattern.second_byte);
+        }
+        let all_candidates = candidates;
+
+        let mut matches = 0;
+        let mut advance = WORD;
+        while candidates != 0 {
+            // Every byte of the word has its own bit 7 in the mask.
+            let offset = candidates.trailing_zeros() as usize / WORD;
+            if !pattern.is_single_byte() {
+                if window.len() < offset + BYTES {
+                    // Leave the rest to the caller, starting at this candidate.
+                    advance = offset;
+                    break;
+                }
+                if !verify(pattern, &window[offset..]) {
+                    // Clear the lowest set bit.
+                    candidates &= candidates - 1;
+                    continue;
+                }
+            }
+            matches |= 1 << offset;
+            candidates &= candidates - 1;
+        }
+
+        if advance == WORD {
+            adaptive.record(all_candidates, matches);
+        }
+        let base = *cursor;
+        *cursor += advance;
+        if matches != 0 {
+            acc = f(acc, base, matches)?;
+        }
+        if advance < WORD {
+            break ControlFlow::Continue(acc);
+        }
+    }
+}
+
+/// Loads the word at `offset` of data.
+#[inline(always)]
+fn word(data: &[u8], offset: usize) -> u64 {
+    let mut word = [0; WORD];
+    word.copy_from_slice(&data[offset..offset + WORD]);
+    u64::from_le_bytes(word)
+}
+
+/// Sets bit 7 of every byte of word that equals byte, and no other bits.
+#[inline(always)]
+const fn equal_bytes(word: u64, byte: u8) -> u64 {
+    // Equal bytes are zero after the xor.
+    let zeros = word ^ u64::from_ne_bytes([byte; WORD]);
+    // Adding 0x7f sets bit 7 of every non-zero byte without carrying into the
+    // next one.
+    !(((zeros & LOW_BITS) + LOW_BITS) | zeros | LOW_BITS)
+}
+
+/// Checks the whole pattern at the start of data, which holds at least
+/// `pattern.len()` bytes.
+fn verify(pattern: &Pattern, data: &[u8]) -> bool {
+    let mut mask = pattern.mask.to_bitmask();
+    while mask != 0 {
+        let index = mask.trailing_zeros() as usize;
+        if data[index] != pattern.bytes[index] {
+            return false;
+        }
+        mask &= mask - 1;
+    }
+    true
+}
@@ -0,0 +1,125 @@
+use core::slice;
+
+use crate::{Pattern, Scanner};
+
+/// Where a match was found in a corpus of several slices, see
+/// [`Pattern::occurrences`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// The index of the slice within the corpus
+    pub slice: usize,
+    /// Where the match starts within the slice
+    pub offset: usize,
+}
+
+/// How often a pattern occurs in a corpus, see [`Pattern::uniqueness`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Uniqueness {
+    /// The pattern doesn't match anywhere.
+    Missing,
+    /// The pattern matches exactly once.
+    Unique(Location),
+    /// The pattern matches `count` times, the first two are given.
+    /// [`Pattern::occurrences`] lists all of them.
+    Ambiguous {
+        first: Location,
+        second: Location,
+        count: usize,
+    },
+}
+
+impl Uniqueness {
+    /// Returns `true` if the pattern matches exactly once.
+    #[inline]
+    pub const fn is_unique(&self) -> bool {
+        matches!(self, Self::Unique(_))
+    }
+}
+
+impl Pattern {
+    /// Checks whether the pattern matches exactly once across all slices of
+    /// `corpus`, e.g. the sections of a binary or several versions of it.
+    /// A signature is only useful if this holds.
+    /// ```
+    /// # use patterns::{Location, Pattern, Uniqueness};
+    /// let pattern = Pattern::new("01 02");
+    /// let corpus: [&[u8]; 2] = [&[0, 1, 2], &[1, 2, 1, 2]];
+    /// assert_eq!(
+    ///     pattern.uniqueness(&corpus),
+    ///     Uniqueness::Ambiguous {
+    ///         first: Location {
+    ///             slice: 0,
+    ///             offset: 1
+    ///         },
+    ///         second: Location {
+    ///             slice: 1,
+    ///             offset: 0
+    ///         },
+    ///         count: 3,
+    ///     }
+    /// );
+    /// assert!(pattern.uniqueness(&corpus[..1]).is_unique());
+    /// ```
+    #[inline]
+    pub fn uniqueness(&self, corpus: &[&[u8]]) -> Uniqueness {
+        let mut occurrences = self.occurrences(corpus);
+        let Some(first) = occurrences.next() else {
+            return Uniqueness::Missing;
+        };
+        let Some(second) = occurrences.next() else {
+            return Uniqueness::Unique(first);
+        };
+        Uniqueness::Ambiguous {
+            first,
+            second,
+            count: 2 + occurrences.count(),
+        }
+    }
+
+    /// Creates an iterator through every match in all slices of `corpus`.
+    /// Matches never span slices.
+    #[inline]
+    pub fn occurrences<'pattern, 'corpus>(
+        &'pattern self,
+        corpus: &'corpus [&'corpus [u8]],
+    ) -> Occurrences<'pattern, 'corpus> {
+        Occurrences {
+            pattern: self,
+            corpus: corpus.iter(),
+            slice: 0,
+            scanner: None,
+        }
+    }
+}
+
+/// An iterator through the matches in a corpus, see
+/// [`Pattern::occurrences`]
+#[must_use]
+pub struct Occurrences<'pattern, 'corpus> {
+    pattern: &'pattern Pattern,
+    corpus: slice::Iter<'corpus, &'corpus [u8]>,
+    // The index of the slice the scanner runs through.
+    slice: usize,
+    scanner: Option<Scanner<'pattern, 'corpus, 'corpus>>,
+}
+
+impl<'pattern, 'corpus> Iterator for Occurrences<'pattern, 'corpus> {
+    type Item = Location;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(offset) = self.scanner.as_mut().and_then(Iterator::next) {
+                return Some(Location {
+                    slice: self.slice,
+                    offset,
+                });
+            }
+            let data = self.corpus.next()?;
+            if self.scanner.is_some() {
+                self.slice += 1;
+            }
+            self.scanner = Some(self.pattern.matches(data));
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use core::{iter::Peekable, mem};
+
+use crate::{Aligned, Pattern};
+
+/// A number that [`ValueScan`] can search for.
+/// Implemented for the integer types and `f32` and `f64`.
+pub trait Value: Copy + PartialEq + private::Sealed {}
+
+mod private {
+    pub trait Sealed {
+        /// Floats can be compared with a tolerance instead of their bytes.
+        const FLOAT: bool;
+        /// Writes the native endian bytes of the value to the start of out.
+        fn write(self, out: &mut [u8]);
+        /// Reads a value from the native endian bytes at the start of bytes.
+        fn read(bytes: &[u8]) -> Self;
+        fn near(self, value: Self, tolerance: Self) -> bool;
+        fn zero() -> Self;
+    }
+}
+
+macro_rules! value {
+    ($float:literal, $zero:literal, |$value:ident, $target:ident, $tolerance:ident| $near:expr, $($ty:ty),*) => {$(
+        impl Value for $ty {}
+
+        impl private::Sealed for $ty {
+            const FLOAT: bool = $float;
+
+            fn write(self, out: &mut [u8]) {
+                out[..mem::size_of::<Self>()].copy_from_slice(&self.to_ne_bytes());
+            }
+
+            fn read(bytes: &[u8]) -> Self {
+                let mut buffer = [0; mem::size_of::<Self>()];
+                buffer.copy_from_slice(&bytes[..mem::size_of::<Self>()]);
+                Self::from_ne_bytes(buffer)
+            }
+
+            fn near(self, value: Self, tolerance: Self) -> bool {
+                let ($value, $target, $tolerance) = (self, value, tolerance);
+                $near
+            }
+
+            fn zero() -> Self {
+                $zero
+            }
+        }
+    )*};
+}
+
+value!(
+    false,
+    0,
+    |value, target, _tolerance| value == target,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    isize
+);
+value!(
+    true,
+    0.0,
+    |value, target, tolerance| value == target || (value - target).abs() <= tolerance,
+    f32,
+    f64
+);
+
+/// A search for a number in memory, like the first scan of a memory editor.
+/// Integers and floats without a tolerance are found with a [`Pattern`] of
+/// their bytes, floats with a tolerance are compared at every aligned offset
+/// instead.
+/// Values are read in native byte order.
+/// ```
+/// # use patterns::ValueScan;
+/// let mut data = [0; 32];
+/// data[3..7].copy_from_slice(&1337_i32.to_ne_bytes());
+/// data[8..12].copy_from_slice(&1337_i32.to_ne_bytes());
+/// let scan = ValueScan::new(1337_i32);
+/// assert_eq!(scan.matches(&data).collect::<Vec<_>>(), [3, 8]);
+/// let scan = scan.aligned(4);
+/// assert_eq!(scan.matches(&data).collect::<Vec<_>>(), [8]);
+///
+/// data[16..20].copy_from_slice(&(0.1_f32 + 0.2).to_ne_bytes());
+/// let scan = ValueScan::new(0.3_f32).aligned(4).tolerance(1e-6);
+/// assert_eq!(scan.matches(&data).collect::<Vec<_>>(), [16]);
+///
+/// data[24..28].copy_from_slice(&f32::INFINITY.to_ne_bytes());
+/// let scan = ValueScan::new(f32::INFINITY).aligned(4).tolerance(1.0);
+/// assert_eq!(scan.matches(&data).collect::<Vec<_>>(), [24]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ValueScan<T> {
+    value: T,
+    tolerance: T,
+    alignment: usize,
+    pattern: Pattern,
+    // The bytes of -0.0 for 0.0 and the other way around, which are equal.
+    negative_zero: Option<Pattern>,
+}
+
+impl<T: Value> ValueScan<T> {
+    /// Creates a search for value at any offset.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        let size = mem::size_of::<T>();
+        let mut bytes = [0; mem::size_of::<u64>()];
+        value.write(&mut bytes);
+        let pattern = Pattern::from_slice(&bytes[..size], u64::MAX);
+        let negative_zero = (T::FLOAT && value == T::zero()).then(|| {
+            // The sign is the top bit of the most significant byte.
+            let sign = if cfg!(target_endian = "little") {
+                size - 1
+            } else {
+                0
+            };
+            bytes[sign] ^= 0x80;
+            Pattern::from_slice(&bytes[..size], u64::MAX)
+        });
+        Self {
+            value,
+            tolerance: T::zero(),
+            alignment: 1,
+            pattern,
+            negative_zero,
+        }
+    }
+
+    /// Only finds values at multiples of `alignment` within data.
+    /// # Panics
+    /// Panics if `alignment` is 0.
+    #[inline]
+    #[must_use]
+    pub fn aligned(mut self, alignment: usize) -> Self {
+        assert!(alignment != 0, "alignment must not be 0");
+        self.alignment = alignment;
+        self
+    }
+
+    /// Creates an iterator through data that yields the offset of every
+    /// value found.
+    #[inline]
+    pub fn matches<'scan, 'data>(&'scan self, data: &'data [u8]) -> ValueMatches<'scan, 'data, T> {
+        let bytes = !T::FLOAT || self.tolerance == T::zero();
+        let scan =
+            |pattern: &'scan Pattern| pattern.matches_aligned(data, self.alignment).peekable();
+        ValueMatches {
+            scan: self,
+            data,
+            next: 0,
+            scanner: bytes.then(|| scan(&self.pattern)),
+            negative_zero: self.negative_zero.as_ref().filter(|_| bytes).map(scan),
+        }
+    }
+}
+
+macro_rules! tolerance {
+    ($($ty:ty),*) => {$(
+        impl ValueScan<$ty> {
+            /// Also finds values that differ by at most `epsilon`, e.g. the
+            /// result of a calculation with rounding errors.
+            /// Without a tolerance, only equal values are found, which
+            /// includes the same NaN.
+            #[inline]
+            #[must_use]
+            pub fn tolerance(mut self, epsilon: $ty) -> Self {
+                self.tolerance = epsilon.abs();
+                self
+            }
+        }
+    )*};
+}
+
+tolerance!(f32, f64);
+
+/// An iterator through the offsets of a value, see [`ValueScan::matches`]
+#[must_use]
+pub struct ValueMatches<'scan, 'data, T> {
+    scan: &'scan ValueScan<T>,
+    data: &'data [u8],
+    // The next offset to compare floats at.
+    next: usize,
+    // Finds the bytes of the value if it is compared without a tolerance.
+    scanner: Option<Peekable<Aligned<'scan, 'data>>>,
+    negative_zero: Option<Peekable<Aligned<'scan, 'data>>>,
+}
+
+impl<'scan, 'data, T: Value> Iterator for ValueMatches<'scan, 'data, T> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(scanner) = &mut self.scanner {
+            let Some(negative_zero) = &mut self.negative_zero else {
+                return scanner.next();
+            };
+            // Merges the matches of both zeros in ascending order.
+            return match (scanner.peek(), negative_zero.peek()) {
+                (Some(zero), Some(negative)) if negative < zero => negative_zero.next(),
+                (None, _) => negative_zero.next(),
+                _ => scanner.next(),
+            };
+        }
+        let size = mem::size_of::<T>();
+        while let Some(bytes) = self.data.get(self.next..self.next.checked_add(size)?) {
+            let index = self.next;
+            self.next = index.saturating_add(self.scan.alignment);
+            if T::read(bytes).near(self.scan.value, self.scan.tolerance) {
+                return Some(index);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,213 @@
+use crate::Pattern;
+
+/// How an [`Xref`] refers to its target
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum XrefKind {
+    /// A little endian 64-bit address, e.g. in a table of pointers
+    Absolute64,
+    /// A little endian 32-bit address, e.g. in 32-bit code
+    Absolute32,
+    /// An x86 rel32 operand or RIP-relative displacement, relative to the end
+    /// of its instruction, e.g. of a `call`, `jmp` or `lea`
+    Relative32,
+}
+
+/// A reference to an address, see [`Xrefs`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Xref {
+    /// Where the address or operand starts within code
+    pub offset: usize,
+    pub kind: XrefKind,
+}
+
+/// The bytes in front of a rel32 operand: `call`, `jmp`, the `0f` of a
+/// conditional jump, and the ModRM bytes of RIP-relative addressing.
+const OPCODES: [u8; 11] = [
+    0xe8, 0xe9, 0x0f, 0x05, 0x0d, 0x15, 0x1d, 0x25, 0x2d, 0x35, 0x3d,
+];
+
+/// The length of the immediate after the displacement of an instruction
+/// with a ModRM byte, or `None` for opcodes that aren't recognized, e.g. a
+/// `05` that is `add eax, imm32` and no ModRM byte at all.
+/// `two_byte` opcodes follow a `0f`. A `66` prefix shrinks 4 byte
+/// immediates to 2.
+const fn immediate(opcode: u8, two_byte: bool, prefix_66: bool) -> Option<usize> {
+    let len = match (two_byte, opcode) {
+        // Moves, loads, arithmetic and `call` or `jmp` through memory.
+        (false, 0x01 | 0x03 | 0x09 | 0x0b | 0x21 | 0x23 | 0x29 | 0x2b | 0x31 | 0x33)
+        | (false, 0x39 | 0x3b | 0x63 | 0x85 | 0x87 | 0x88 | 0x89 | 0x8a | 0x8b | 0x8d | 0xff) => 0,
+        (false, 0x80 | 0x83 | 0xc6) => 1,
+        (false, 0x81 | 0xc7) => 4,
+        // SSE moves and compares, `imul`, `movzx` and `movsx`.
+        (true, 0x10 | 0x11 | 0x28 | 0x29 | 0x2e | 0x2f | 0x6f | 0x7f | 0xaf)
+        | (true, 0xb6 | 0xb7 | 0xbe | 0xbf) => 0,
+        _ => return None,
+    };
+    Some(if len == 4 && prefix_66 { 2 } else { len })
+}
+
+/// An iterator through code that yields every reference to an address, e.g.
+/// the instructions that use a string found with a pattern.
+/// Absolute addresses are searched as literal patterns, so they are found
+/// in data as well. Rel32 operands are only checked behind a `call`, `jmp`,
+/// conditional jump or RIP-relative ModRM byte, which are searched with
+/// patterns as well. ModRM bytes only count behind a common opcode that
+/// uses memory, which also tells the length of an immediate behind the
+/// displacement.
+/// Yields references in ascending order of their offsets.
+/// ```
+/// # use patterns::{Xref, XrefKind, Xrefs};
+/// let base = 0x1000;
+/// let string = 0x2000_u64;
+/// // lea rcx, [rip + rel32] at 0x1000, the operand ends at 0x1007.
+/// let mut code = vec![0x48, 0x8d, 0x0d];
+/// code.extend((string as i32 - 0x1007).to_le_bytes());
+/// code.extend(string.to_le_bytes());
+/// // call rel32 at 0x100f, the operand ends at 0x1014.
+/// code.push(0xe8);
+/// code.extend((string as i32 - 0x1014).to_le_bytes());
+/// // cmp dword [rip + rel32], 1 at 0x1014, the instruction ends at 0x101b.
+/// code.extend([0x83, 0x3d]);
+/// code.extend((string as i32 - 0x101b).to_le_bytes());
+/// code.push(1);
+/// let found = Xrefs::new(&code, base, string).collect::<Vec<_>>();
+/// assert_eq!(
+///     found,
+///     [
+///         Xref {
+///             offset: 3,
+///             kind: XrefKind::Relative32
+///         },
+///         Xref {
+///             offset: 7,
+///             kind: XrefKind::Absolute64
+///         },
+///         Xref {
+///             offset: 16,
+///             kind: XrefKind::Relative32
+///         },
+///         Xref {
+///             offset: 22,
+///             kind: XrefKind::Relative32
+///         },
+///     ]
+/// );
+/// ```
+#[must_use]
+pub struct Xrefs<'code> {
+    code: &'code [u8],
+    base: u64,
+    target: u64,
+    absolute64: Pattern,
+    absolute32: Option<Pattern>,
+    opcodes: [Pattern; OPCODES.len()],
+    // The offset of the next reference each pattern points to, absolute ones
+    // first. `None` once a pattern has no more matches.
+    next: [Option<usize>; OPCODES.len() + 2],
+}
+
+impl<'code> Xrefs<'code> {
+    /// Searches code that is loaded at address `base` for references to
+    /// `target`.
+    #[inline]
+    pub fn new(code: &'code [u8], base: u64, target: u64) -> Self {
+        let absolute32 = u32::try_from(target)
+            .ok()
+            .map(|target| Pattern::from_slice(&target.to_le_bytes(), u64::MAX));
+        let mut xrefs = Self {
+            code,
+            base,
+            target,
+            absolute64: Pattern::from_slice(&target.to_le_bytes(), u64::MAX),
+            absolute32,
+            opcodes: OPCODES.map(|opcode| Pattern::from_slice(&[opcode], u64::MAX)),
+            next: [None; OPCODES.len() + 2],
+        };
+        for index in 0..xrefs.next.len() {
+            xrefs.next[index] = xrefs.search(index, 0);
+        }
+        xrefs
+    }
+
+    /// The offset of the first reference at or after `from` that the pattern
+    /// at index in `next` points to.
+    fn search(&self, index: usize, from: usize) -> Option<usize> {
+        let pattern = match index {
+            0 => &self.absolute64,
+            1 => self.absolute32.as_ref()?,
+            _ => &self.opcodes[index - 2],
+        };
+        // Operands follow their opcode, `0f 8x` is two bytes long.
+        let operand = match index {
+            0 | 1 => 0,
+            _ if OPCODES[index - 2] == 0x0f => 2,
+            _ => 1,
+        };
+        let start = from.saturating_sub(operand);
+        let found = pattern.find(self.code.get(start..)?)?;
+        Some(start + found + operand)
+    }
+
+    /// The kind of reference at offset that the pattern at index in `next`
+    /// found, if it really is one.
+    fn check(&self, index: usize, offset: usize) -> Option<XrefKind> {
+        match index {
+            0 => Some(XrefKind::Absolute64),
+            // A 64-bit address is reported as such.
+            1 => (!self.absolute64.matches_at(self.code, offset)).then_some(XrefKind::Absolute32),
+            _ => {
+                // Conditional jumps are `0f 80` to `0f 8f`.
+                let byte = |offset: Option<usize>| self.code.get(offset?).copied();
+                let before = |count: usize| byte(offset.checked_sub(count));
+                let immediate = match OPCODES[index - 2] {
+                    0xe8 | 0xe9 => 0,
+                    // Conditional jumps are `0f 80` to `0f 8f`.
+                    0x0f if before(1).is_some_and(|byte| byte & 0xf0 == 0x80) => 0,
+                    0x0f => return None,
+                    _ => {
+                        let opcode = before(2)?;
+                        let two_byte =
+                            before(3) == Some(0x0f) && immediate(opcode, true, false).is_some();
+                        let opcode_at = offset - if two_byte { 3 } else { 2 };
+                        // A REX prefix can sit between `66` and the opcode.
+                        let mut prefix = byte(opcode_at.checked_sub(1));
+                        if prefix.is_some_and(|byte| byte & 0xf0 == 0x40) {
+                            prefix = byte(opcode_at.checked_sub(2));
+                        }
+                        immediate(opcode, two_byte, prefix == Some(0x66))?
+                    }
+                };
+                let operand = self.code.get(offset..offset + 4)?;
+                let operand = i32::from_le_bytes(operand.try_into().ok()?);
+                let end = self.base.wrapping_add((offset + 4 + immediate) as u64);
+                (end.wrapping_add_signed(i64::from(operand)) == self.target)
+                    .then_some(XrefKind::Relative32)
+            }
+        }
+    }
+}
+
+impl<'code> Iterator for Xrefs<'code> {
+    type Item = Xref;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Relative operands come before absolute addresses at the same
+            // offset. No two opcodes find the same operand, as the byte in
+            // front of it differs.
+            let (index, offset) = self
+                .next
+                .iter()
+                .enumerate()
+                .filter_map(|(index, next)| Some((index, (*next)?)))
+                .min_by_key(|&(index, offset)| (offset, index < 2, index))?;
+            let kind = self.check(index, offset);
+            self.next[index] = self.search(index, offset + 1);
+            if let Some(kind) = kind {
+                return Some(Xref { offset, kind });
+            }
+        }
+    }
+}
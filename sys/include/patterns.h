@@ -0,0 +1,320 @@
+/* Generated by the header test of patterns-sys, do not edit. */
+
+#ifndef PATTERNS_H
+#define PATTERNS_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/* Opaque, allocate pattern_struct_size() bytes aligned to pattern_struct_align(). */
+typedef struct Pattern Pattern;
+
+/**
+ * The version of the C API, bumped on every incompatible change.
+ * `patterns.h` defines it as well, compare it with [`patterns_abi_version`]
+ * to detect a mismatched library at runtime.
+ */
+#define PATTERNS_ABI_VERSION 3
+
+/**
+ * Whether [`parse_pattern`] filled its buffer, and why not.
+ */
+typedef enum PatternStatus {
+  /**
+   * The pattern was parsed.
+   */
+  PatternStatus_Ok = 0,
+  /**
+   * A pointer argument was null.
+   */
+  PatternStatus_NullPointer,
+  /**
+   * The pattern is not valid UTF-8.
+   */
+  PatternStatus_InvalidUtf8,
+  /**
+   * The pattern has more bytes than fit into a [`Pattern`].
+   */
+  PatternStatus_PatternTooLong,
+  /**
+   * A byte of the pattern is neither hex nor a wildcard.
+   */
+  PatternStatus_InvalidHexNumber,
+  /**
+   * The pattern consists of wildcards only.
+   */
+  PatternStatus_MissingNonWildcardByte,
+  /**
+   * The pattern is invalid for another reason.
+   */
+  PatternStatus_InvalidPattern,
+  /**
+   * The library panicked. This is a bug.
+   */
+  PatternStatus_Panicked,
+} PatternStatus;
+
+/**
+ * An iterator through data that can be resumed from C, see
+ * [`scanner_create`]. Opaque to callers.
+ */
+typedef struct ScannerHandle ScannerHandle;
+
+/**
+ * A stream of chunks searched from C, see [`stream_create`]. Opaque to
+ * callers.
+ */
+typedef struct StreamHandle StreamHandle;
+
+/**
+ * A match with the information C callers can't look up on their own.
+ */
+typedef struct PatternMatch {
+  /**
+   * The offset of the match within data, plus the base address passed to
+   * the search
+   */
+  uint64_t offset;
+  /**
+   * The number of bytes the match spans
+   */
+  uint32_t length;
+  /**
+   * The index of the pattern that matched, 0 if there is only one
+   */
+  uint32_t pattern_id;
+} PatternMatch;
+
+/**
+ * Called with every match found by [`match_pattern_cb`], together with the
+ * `user_data` passed to it. Returning 0 stops the search.
+ */
+typedef int32_t (*MatchCallback)(uint64_t offset, void *user_data);
+
+/**
+ * The [`PATTERNS_ABI_VERSION`] this library was built with.
+ */
+uint32_t patterns_abi_version(void);
+
+/**
+ * The size in bytes of the buffer that [`parse_pattern`] fills.
+ */
+size_t pattern_struct_size(void);
+
+/**
+ * The alignment in bytes of the buffer that [`parse_pattern`] fills.
+ */
+size_t pattern_struct_align(void);
+
+/**
+ * # Safety
+ * `len` must be a valid length of `pat`. On success, the content of `res` will
+ * not be null. There is no guarantee about the layout of `res` and it should
+ * be considered opaque. The buffer behind `res` must be of size
+ * [`pattern_struct_size`] and needs to be aligned to [`pattern_struct_align`].
+ * `pat` needs to be valid UTF-8.
+ * Returns [`PatternStatus::Ok`] on success and leaves `res` untouched
+ * otherwise.
+ */
+enum PatternStatus parse_pattern(const uint8_t *pat, size_t len, Pattern *res);
+
+/**
+ * Parses a pattern into memory owned by the library, for callers that can't
+ * allocate aligned buffers themselves, e.g. .NET through a `SafeHandle`.
+ * The pattern can be passed everywhere a pattern filled by [`parse_pattern`]
+ * is expected.
+ * # Safety
+ * [in] `pat` is the pattern, it needs to be valid UTF-8
+ * [in] `len` must be the number of bytes of `pat`.
+ * [out] `status` will be filled with the result of parsing, unless it is null
+ * [return] returns the pattern to pass to [`pattern_free`], or null if
+ * parsing failed
+ */
+Pattern *pattern_new(const uint8_t *pat, size_t len, enum PatternStatus *status);
+
+/**
+ * # Safety
+ * [in] `pat` must be a pointer returned by [`pattern_new`] or null. It must
+ * not be used afterwards.
+ */
+void pattern_free(Pattern *pat);
+
+/**
+ * # Safety
+ * [in] `pat` must be the same pointer that was filled by [`parse_pattern`].
+ * [in] `data` is the data to search through
+ * [in] `len` must be the number of bytes of `data`.
+ * [out] `res` will be filled with the result
+ * [in] `res_len` is the amount of results that fit into `res`
+ * [return] returns how many offsets were written, at most `res_len`. Use
+ * [`match_pattern_results`] to learn how many matches there are in total,
+ * or to add a base address. This function keeps its original signature for
+ * existing callers, so it has none.
+ */
+size_t match_pattern(const Pattern *pat,
+                     const uint8_t *data,
+                     size_t len,
+                     size_t *res,
+                     size_t res_len);
+
+/**
+ * Writes a pattern into a versioned buffer that [`pattern_load`] reads back,
+ * independent of the endianness of the machine.
+ * # Safety
+ * [in] `pat` must be the same pointer that was filled by [`parse_pattern`].
+ * [out] `buf` will be filled with the saved pattern
+ * [in] `cap` is the amount of bytes that fit into `buf`
+ * [return] returns the length of the saved pattern. Nothing is written if it
+ * is larger than `cap`, call again with a larger buffer.
+ */
+size_t pattern_save(const Pattern *pat, uint8_t *buf, size_t cap);
+
+/**
+ * Reads a pattern written by [`pattern_save`].
+ * # Safety
+ * [in] `buf` is the saved pattern
+ * [in] `len` must be the number of bytes of `buf`.
+ * [out] `res` will be filled with the pattern, see [`parse_pattern`] for its
+ * requirements
+ * [return] returns 1, or 0 and leaves `res` untouched if `buf` doesn't hold
+ * a pattern of this version
+ */
+int32_t pattern_load(const uint8_t *buf, size_t len, Pattern *res);
+
+/**
+ * # Safety
+ * [in] `pat` must be the same pointer that was filled by [`parse_pattern`].
+ * [in] `data` is the data to search through
+ * [in] `len` must be the number of bytes of `data`.
+ * [in] `base_address` is added to every offset, e.g. the address `data` was
+ * read from in another process
+ * [out] `res` will be filled with the matches
+ * [in] `res_len` is the amount of results that fit into `res`
+ * [return] returns how many matches were found. If that is more than
+ * `res_len`, only the first `res_len` were written.
+ */
+size_t match_pattern_results(const Pattern *pat,
+                             const uint8_t *data,
+                             size_t len,
+                             uint64_t base_address,
+                             struct PatternMatch *res,
+                             size_t res_len);
+
+/**
+ * Searches several patterns in a single pass through data.
+ * # Safety
+ * [in] `pats` must point to `pats_len` consecutive patterns, each filled by
+ * [`parse_pattern`].
+ * [in] `data` is the data to search through
+ * [in] `len` must be the number of bytes of `data`.
+ * [in] `base_address` is added to every offset, e.g. the address `data` was
+ * read from in another process
+ * [out] `res` will be filled with the matches, `pattern_id` is the index into
+ * `pats`. Matches of the same pattern are in ascending order, matches of
+ * different patterns can be interleaved.
+ * [in] `res_len` is the amount of results that fit into `res`
+ * [return] returns how many matches were found. If that is more than
+ * `res_len`, only the first `res_len` were written.
+ */
+size_t match_patterns(const Pattern *pats,
+                      size_t pats_len,
+                      const uint8_t *data,
+                      size_t len,
+                      uint64_t base_address,
+                      struct PatternMatch *res,
+                      size_t res_len);
+
+/**
+ * # Safety
+ * [in] `pat` must be the same pointer that was filled by [`parse_pattern`].
+ * [in] `data` is the data to search through
+ * [in] `len` must be the number of bytes of `data`.
+ * [in] `base_address` is added to every offset, e.g. the address `data` was
+ * read from in another process
+ * [in] `cb` is called with the offset of every match, in ascending order
+ * [in] `user_data` is passed to `cb` unchanged
+ * [return] returns how many times `cb` was called
+ */
+size_t match_pattern_cb(const Pattern *pat,
+                        const uint8_t *data,
+                        size_t len,
+                        uint64_t base_address,
+                        MatchCallback cb,
+                        void *user_data);
+
+/**
+ * # Safety
+ * [in] `pat` must be the same pointer that was filled by [`parse_pattern`].
+ * [in] `data` is the data to search through
+ * [in] `len` must be the number of bytes of `data`.
+ * [in] `base_address` is added to every offset, e.g. the address `data` was
+ * read from in another process
+ * [return] returns a handle to pass to [`scanner_next`], or null if any
+ * pointer is null. `pat` and `data` must stay valid and unchanged until the
+ * handle is passed to [`scanner_destroy`].
+ */
+struct ScannerHandle *scanner_create(const Pattern *pat,
+                                     const uint8_t *data,
+                                     size_t len,
+                                     uint64_t base_address);
+
+/**
+ * # Safety
+ * [in] `handle` must be a pointer returned by [`scanner_create`] that was not
+ * destroyed yet.
+ * [out] `res` will be filled with the offset of the next match
+ * [return] returns 1 if a match was found, or 0 and leaves `res` untouched
+ * if not
+ */
+int32_t scanner_next(struct ScannerHandle *handle, uint64_t *res);
+
+/**
+ * # Safety
+ * [in] `handle` must be a pointer returned by [`scanner_create`] or null. It
+ * must not be used afterwards.
+ */
+void scanner_destroy(struct ScannerHandle *handle);
+
+/**
+ * # Safety
+ * [in] `pat` must be the same pointer that was filled by [`parse_pattern`]. It
+ * must stay valid and unchanged until the handle is passed to
+ * [`stream_finish`].
+ * [in] `base_address` is added to every offset, e.g. the address the stream
+ * starts at in another process
+ * [return] returns a handle to pass to [`stream_feed`], or null if `pat` is
+ * null
+ */
+struct StreamHandle *stream_create(const Pattern *pat, uint64_t base_address);
+
+/**
+ * Appends a chunk to the stream. Finds matches that span the previous chunks.
+ * # Safety
+ * [in] `handle` must be a pointer returned by [`stream_create`] that was not
+ * finished yet.
+ * [in] `data` is the next chunk of the stream
+ * [in] `len` must be the number of bytes of `data`.
+ * [out] `res` will be filled with the offsets from the start of the stream,
+ * plus the base address, of all matches that end within this chunk
+ * [in] `res_len` is the amount of results that fit into `res`
+ * [return] returns how many matches end within this chunk. If that is more
+ * than `res_len`, only the first `res_len` were written. The stream moves on
+ * either way, so feed smaller chunks to get all of them.
+ */
+size_t stream_feed(struct StreamHandle *handle,
+                   const uint8_t *data,
+                   size_t len,
+                   uint64_t *res,
+                   size_t res_len);
+
+/**
+ * # Safety
+ * [in] `handle` must be a pointer returned by [`stream_create`] or null. It
+ * must not be used afterwards.
+ */
+void stream_finish(struct StreamHandle *handle);
+
+#endif /* PATTERNS_H */
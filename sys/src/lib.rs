@@ -1,24 +1,132 @@
-use core::{slice, str::from_utf8};
+use core::{ffi::c_void, mem, ptr, slice, str::from_utf8};
+use std::panic::{self, AssertUnwindSafe};
 
-use patterns::Pattern;
+use patterns::{MultiScanner, ParsePatternError, Pattern, Prefilter, Scanner, StreamScanner};
+
+/// Whether [`parse_pattern`] filled its buffer, and why not.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternStatus {
+    /// The pattern was parsed.
+    Ok = 0,
+    /// A pointer argument was null.
+    NullPointer,
+    /// The pattern is not valid UTF-8.
+    InvalidUtf8,
+    /// The pattern has more bytes than fit into a [`Pattern`].
+    PatternTooLong,
+    /// A byte of the pattern is neither hex nor a wildcard.
+    InvalidHexNumber,
+    /// The pattern consists of wildcards only.
+    MissingNonWildcardByte,
+    /// The pattern is invalid for another reason.
+    InvalidPattern,
+    /// The library panicked. This is a bug.
+    Panicked,
+}
+
+/// Runs the body of an entry point and returns `fallback` if it panics, a
+/// panic must never unwind into the caller.
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+/// The version of the C API, bumped on every incompatible change.
+/// `patterns.h` defines it as well, compare it with [`patterns_abi_version`]
+/// to detect a mismatched library at runtime.
+pub const PATTERNS_ABI_VERSION: u32 = 3;
+
+/// The [`PATTERNS_ABI_VERSION`] this library was built with.
+#[no_mangle]
+pub extern "C" fn patterns_abi_version() -> u32 {
+    PATTERNS_ABI_VERSION
+}
+
+/// The size in bytes of the buffer that [`parse_pattern`] fills.
+#[no_mangle]
+pub extern "C" fn pattern_struct_size() -> usize {
+    mem::size_of::<Pattern>()
+}
+
+/// The alignment in bytes of the buffer that [`parse_pattern`] fills.
+#[no_mangle]
+pub extern "C" fn pattern_struct_align() -> usize {
+    mem::align_of::<Pattern>()
+}
 
 /// # Safety
 /// `len` must be a valid length of `pat`. On success, the content of `res` will
 /// not be null. There is no guarantee about the layout of `res` and it should
-/// be considered opaque. The buffer behind `res` must be of size 256 bytes and
-/// needs to be aligned to [`patterns::BYTES`] bytes! By default 64.
+/// be considered opaque. The buffer behind `res` must be of size
+/// [`pattern_struct_size`] and needs to be aligned to [`pattern_struct_align`].
 /// `pat` needs to be valid UTF-8.
+/// Returns [`PatternStatus::Ok`] on success and leaves `res` untouched
+/// otherwise.
 #[no_mangle]
-pub unsafe extern "C" fn parse_pattern(pat: *const u8, len: usize, res: *mut Pattern) {
-    if pat.is_null() || res.is_null() {
-        return;
-    }
-    if let Some(pattern) = from_utf8(slice::from_raw_parts(pat, len))
-        .ok()
-        .and_then(|x| x.parse().ok())
-    {
-        *res = pattern;
-    }
+pub unsafe extern "C" fn parse_pattern(
+    pat: *const u8,
+    len: usize,
+    res: *mut Pattern,
+) -> PatternStatus {
+    guard(PatternStatus::Panicked, || {
+        if pat.is_null() || res.is_null() {
+            return PatternStatus::NullPointer;
+        }
+        let Ok(pattern) = from_utf8(slice::from_raw_parts(pat, len)) else {
+            return PatternStatus::InvalidUtf8;
+        };
+        match pattern.parse() {
+            Ok(pattern) => {
+                *res = pattern;
+                PatternStatus::Ok
+            }
+            Err(ParsePatternError::PatternTooLong) => PatternStatus::PatternTooLong,
+            Err(ParsePatternError::InvalidHexNumber(_)) => PatternStatus::InvalidHexNumber,
+            Err(ParsePatternError::MissingNonWildcardByte) => PatternStatus::MissingNonWildcardByte,
+            Err(_) => PatternStatus::InvalidPattern,
+        }
+    })
+}
+
+/// Parses a pattern into memory owned by the library, for callers that can't
+/// allocate aligned buffers themselves, e.g. .NET through a `SafeHandle`.
+/// The pattern can be passed everywhere a pattern filled by [`parse_pattern`]
+/// is expected.
+/// # Safety
+/// [in] `pat` is the pattern, it needs to be valid UTF-8
+/// [in] `len` must be the number of bytes of `pat`.
+/// [out] `status` will be filled with the result of parsing, unless it is null
+/// [return] returns the pattern to pass to [`pattern_free`], or null if
+/// parsing failed
+#[no_mangle]
+pub unsafe extern "C" fn pattern_new(
+    pat: *const u8,
+    len: usize,
+    status: *mut PatternStatus,
+) -> *mut Pattern {
+    guard(ptr::null_mut(), || {
+        let mut pattern = Box::<Pattern>::new_uninit();
+        let result = parse_pattern(pat, len, pattern.as_mut_ptr());
+        if !status.is_null() {
+            *status = result;
+        }
+        if result != PatternStatus::Ok {
+            return ptr::null_mut();
+        }
+        Box::into_raw(pattern.assume_init())
+    })
+}
+
+/// # Safety
+/// [in] `pat` must be a pointer returned by [`pattern_new`] or null. It must
+/// not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pattern_free(pat: *mut Pattern) {
+    guard((), || {
+        if !pat.is_null() {
+            drop(Box::from_raw(pat));
+        }
+    })
 }
 
 /// # Safety
@@ -27,7 +135,10 @@ pub unsafe extern "C" fn parse_pattern(pat: *const u8, len: usize, res: *mut Pat
 /// [in] `len` must be the number of bytes of `data`.
 /// [out] `res` will be filled with the result
 /// [in] `res_len` is the amount of results that fit into `res`
-/// [return] returns how many offsets were found
+/// [return] returns how many offsets were written, at most `res_len`. Use
+/// [`match_pattern_results`] to learn how many matches there are in total,
+/// or to add a base address. This function keeps its original signature for
+/// existing callers, so it has none.
 #[no_mangle]
 pub unsafe extern "C" fn match_pattern(
     pat: *const Pattern,
@@ -36,22 +147,386 @@ pub unsafe extern "C" fn match_pattern(
     res: *mut usize,
     res_len: usize,
 ) -> usize {
-    if pat.is_null() || res.is_null() || data.is_null() {
-        return 0;
-    }
-    let data = slice::from_raw_parts(data, len);
-    let res = slice::from_raw_parts_mut(res, res_len);
-    let pattern = &*pat;
-    let mut scan = pattern.matches(data);
-    for (index, element) in res.iter_mut().enumerate() {
-        let Some(offset) = scan.next() else {
-            return index;
+    guard(0, || {
+        if pat.is_null() || res.is_null() || data.is_null() {
+            return 0;
+        }
+        let data = slice::from_raw_parts(data, len);
+        let res = slice::from_raw_parts_mut(res, res_len);
+        let pattern = &*pat;
+        pattern.matches(data).collect_into_slice(res).0
+    })
+}
+
+/// The version of the layout written by [`pattern_save`].
+const SAVE_VERSION: u8 = 1;
+/// Version, length, prefilter and the mask in little endian.
+const SAVE_HEADER: usize = 3 + 8;
+
+/// Writes a pattern into a versioned buffer that [`pattern_load`] reads back,
+/// independent of the endianness of the machine.
+/// # Safety
+/// [in] `pat` must be the same pointer that was filled by [`parse_pattern`].
+/// [out] `buf` will be filled with the saved pattern
+/// [in] `cap` is the amount of bytes that fit into `buf`
+/// [return] returns the length of the saved pattern. Nothing is written if it
+/// is larger than `cap`, call again with a larger buffer.
+#[no_mangle]
+pub unsafe extern "C" fn pattern_save(pat: *const Pattern, buf: *mut u8, cap: usize) -> usize {
+    guard(0, || {
+        if pat.is_null() {
+            return 0;
+        }
+        let pattern = &*pat;
+        let bytes = pattern.bytes();
+        let len = SAVE_HEADER + bytes.len();
+        if buf.is_null() || cap < len {
+            return len;
+        }
+        let buf = slice::from_raw_parts_mut(buf, len);
+        buf[0] = SAVE_VERSION;
+        // Patterns are at most 64 bytes long.
+        buf[1] = bytes.len() as u8;
+        buf[2] = match pattern.prefilter() {
+            Prefilter::TwoBytes => 1,
+            _ => 0,
         };
+        buf[3..SAVE_HEADER].copy_from_slice(&pattern.mask().to_le_bytes());
+        buf[SAVE_HEADER..].copy_from_slice(bytes);
+        len
+    })
+}
+
+/// Reads a pattern written by [`pattern_save`].
+/// # Safety
+/// [in] `buf` is the saved pattern
+/// [in] `len` must be the number of bytes of `buf`.
+/// [out] `res` will be filled with the pattern, see [`parse_pattern`] for its
+/// requirements
+/// [return] returns 1, or 0 and leaves `res` untouched if `buf` doesn't hold
+/// a pattern of this version
+#[no_mangle]
+pub unsafe extern "C" fn pattern_load(buf: *const u8, len: usize, res: *mut Pattern) -> i32 {
+    guard(0, || {
+        if buf.is_null() || res.is_null() || len < SAVE_HEADER {
+            return 0;
+        }
+        let buf = slice::from_raw_parts(buf, len);
+        if buf[0] != SAVE_VERSION || len != SAVE_HEADER + usize::from(buf[1]) {
+            return 0;
+        }
+        let prefilter = match buf[2] {
+            0 => Prefilter::FirstByte,
+            1 => Prefilter::TwoBytes,
+            _ => return 0,
+        };
+        let mut mask = [0; 8];
+        mask.copy_from_slice(&buf[3..SAVE_HEADER]);
+        match Pattern::try_from_slice(&buf[SAVE_HEADER..], u64::from_le_bytes(mask)) {
+            Ok(pattern) => {
+                *res = pattern.with_prefilter(prefilter);
+                1
+            }
+            Err(_) => 0,
+        }
+    })
+}
+
+/// A match with the information C callers can't look up on their own.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PatternMatch {
+    /// The offset of the match within data, plus the base address passed to
+    /// the search
+    pub offset: u64,
+    /// The number of bytes the match spans
+    pub length: u32,
+    /// The index of the pattern that matched, 0 if there is only one
+    pub pattern_id: u32,
+}
 
-        *element = offset;
+impl PatternMatch {
+    fn new(pattern: &Pattern, pattern_id: usize, base_address: u64, offset: usize) -> Self {
+        Self {
+            offset: base_address.wrapping_add(offset as u64),
+            // Patterns are at most 64 bytes long.
+            length: pattern.bytes().len() as u32,
+            pattern_id: pattern_id as u32,
+        }
     }
+}
+
+/// Fills `res` with the first matches and counts the rest.
+fn fill<T, I: Iterator<Item = T>>(res: &mut [T], mut matches: I) -> usize {
+    let written = res
+        .iter_mut()
+        .zip(&mut matches)
+        .map(|(slot, found)| *slot = found)
+        .count();
+    written + matches.count()
+}
+
+/// # Safety
+/// [in] `pat` must be the same pointer that was filled by [`parse_pattern`].
+/// [in] `data` is the data to search through
+/// [in] `len` must be the number of bytes of `data`.
+/// [in] `base_address` is added to every offset, e.g. the address `data` was
+/// read from in another process
+/// [out] `res` will be filled with the matches
+/// [in] `res_len` is the amount of results that fit into `res`
+/// [return] returns how many matches were found. If that is more than
+/// `res_len`, only the first `res_len` were written.
+#[no_mangle]
+pub unsafe extern "C" fn match_pattern_results(
+    pat: *const Pattern,
+    data: *const u8,
+    len: usize,
+    base_address: u64,
+    res: *mut PatternMatch,
+    res_len: usize,
+) -> usize {
+    guard(0, || {
+        if pat.is_null() || res.is_null() || data.is_null() {
+            return 0;
+        }
+        let data = slice::from_raw_parts(data, len);
+        let res = slice::from_raw_parts_mut(res, res_len);
+        let pattern = &*pat;
+        let matches = pattern
+            .matches(data)
+            .map(|offset| PatternMatch::new(pattern, 0, base_address, offset));
+        fill(res, matches)
+    })
+}
+
+/// Searches several patterns in a single pass through data.
+/// # Safety
+/// [in] `pats` must point to `pats_len` consecutive patterns, each filled by
+/// [`parse_pattern`].
+/// [in] `data` is the data to search through
+/// [in] `len` must be the number of bytes of `data`.
+/// [in] `base_address` is added to every offset, e.g. the address `data` was
+/// read from in another process
+/// [out] `res` will be filled with the matches, `pattern_id` is the index into
+/// `pats`. Matches of the same pattern are in ascending order, matches of
+/// different patterns can be interleaved.
+/// [in] `res_len` is the amount of results that fit into `res`
+/// [return] returns how many matches were found. If that is more than
+/// `res_len`, only the first `res_len` were written.
+#[no_mangle]
+pub unsafe extern "C" fn match_patterns(
+    pats: *const Pattern,
+    pats_len: usize,
+    data: *const u8,
+    len: usize,
+    base_address: u64,
+    res: *mut PatternMatch,
+    res_len: usize,
+) -> usize {
+    guard(0, || {
+        if pats.is_null() || res.is_null() || data.is_null() {
+            return 0;
+        }
+        let patterns = slice::from_raw_parts(pats, pats_len);
+        let data = slice::from_raw_parts(data, len);
+        let res = slice::from_raw_parts_mut(res, res_len);
+        let matches = MultiScanner::new(patterns, data)
+            .map(|(id, offset)| PatternMatch::new(&patterns[id], id, base_address, offset));
+        fill(res, matches)
+    })
+}
+
+/// Called with every match found by [`match_pattern_cb`], together with the
+/// `user_data` passed to it. Returning 0 stops the search.
+pub type MatchCallback = Option<unsafe extern "C" fn(offset: u64, user_data: *mut c_void) -> i32>;
+
+/// # Safety
+/// [in] `pat` must be the same pointer that was filled by [`parse_pattern`].
+/// [in] `data` is the data to search through
+/// [in] `len` must be the number of bytes of `data`.
+/// [in] `base_address` is added to every offset, e.g. the address `data` was
+/// read from in another process
+/// [in] `cb` is called with the offset of every match, in ascending order
+/// [in] `user_data` is passed to `cb` unchanged
+/// [return] returns how many times `cb` was called
+#[no_mangle]
+pub unsafe extern "C" fn match_pattern_cb(
+    pat: *const Pattern,
+    data: *const u8,
+    len: usize,
+    base_address: u64,
+    cb: MatchCallback,
+    user_data: *mut c_void,
+) -> usize {
+    guard(0, || {
+        let Some(cb) = cb else {
+            return 0;
+        };
+        if pat.is_null() || data.is_null() {
+            return 0;
+        }
+        let data = slice::from_raw_parts(data, len);
+        let pattern = &*pat;
+        let mut calls = 0;
+        for offset in pattern.matches(data) {
+            calls += 1;
+            if cb(base_address.wrapping_add(offset as u64), user_data) == 0 {
+                break;
+            }
+        }
+        calls
+    })
+}
+
+/// An iterator through data that can be resumed from C, see
+/// [`scanner_create`]. Opaque to callers.
+pub struct ScannerHandle {
+    scanner: Scanner<'static, 'static, 'static>,
+    base_address: u64,
+}
+
+/// # Safety
+/// [in] `pat` must be the same pointer that was filled by [`parse_pattern`].
+/// [in] `data` is the data to search through
+/// [in] `len` must be the number of bytes of `data`.
+/// [in] `base_address` is added to every offset, e.g. the address `data` was
+/// read from in another process
+/// [return] returns a handle to pass to [`scanner_next`], or null if any
+/// pointer is null. `pat` and `data` must stay valid and unchanged until the
+/// handle is passed to [`scanner_destroy`].
+#[no_mangle]
+pub unsafe extern "C" fn scanner_create(
+    pat: *const Pattern,
+    data: *const u8,
+    len: usize,
+    base_address: u64,
+) -> *mut ScannerHandle {
+    guard(ptr::null_mut(), || {
+        if pat.is_null() || data.is_null() {
+            return ptr::null_mut();
+        }
+        // The caller keeps both alive for as long as the handle.
+        let data = slice::from_raw_parts(data, len);
+        let pattern = &*pat;
+        Box::into_raw(Box::new(ScannerHandle {
+            scanner: pattern.matches(data),
+            base_address,
+        }))
+    })
+}
 
-    res_len
+/// # Safety
+/// [in] `handle` must be a pointer returned by [`scanner_create`] that was not
+/// destroyed yet.
+/// [out] `res` will be filled with the offset of the next match
+/// [return] returns 1 if a match was found, or 0 and leaves `res` untouched
+/// if not
+#[no_mangle]
+pub unsafe extern "C" fn scanner_next(handle: *mut ScannerHandle, res: *mut u64) -> i32 {
+    guard(0, || {
+        if handle.is_null() || res.is_null() {
+            return 0;
+        }
+        let handle = &mut *handle;
+        match handle.scanner.next() {
+            Some(offset) => {
+                *res = handle.base_address.wrapping_add(offset as u64);
+                1
+            }
+            None => 0,
+        }
+    })
+}
+
+/// # Safety
+/// [in] `handle` must be a pointer returned by [`scanner_create`] or null. It
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn scanner_destroy(handle: *mut ScannerHandle) {
+    guard((), || {
+        if !handle.is_null() {
+            drop(Box::from_raw(handle));
+        }
+    })
+}
+
+/// A stream of chunks searched from C, see [`stream_create`]. Opaque to
+/// callers.
+pub struct StreamHandle {
+    stream: StreamScanner<'static>,
+    base_address: u64,
+}
+
+/// # Safety
+/// [in] `pat` must be the same pointer that was filled by [`parse_pattern`]. It
+/// must stay valid and unchanged until the handle is passed to
+/// [`stream_finish`].
+/// [in] `base_address` is added to every offset, e.g. the address the stream
+/// starts at in another process
+/// [return] returns a handle to pass to [`stream_feed`], or null if `pat` is
+/// null
+#[no_mangle]
+pub unsafe extern "C" fn stream_create(
+    pat: *const Pattern,
+    base_address: u64,
+) -> *mut StreamHandle {
+    guard(ptr::null_mut(), || {
+        if pat.is_null() {
+            return ptr::null_mut();
+        }
+        // The caller keeps the pattern alive for as long as the handle.
+        let pattern = &*pat;
+        Box::into_raw(Box::new(StreamHandle {
+            stream: StreamScanner::new(pattern),
+            base_address,
+        }))
+    })
+}
+
+/// Appends a chunk to the stream. Finds matches that span the previous chunks.
+/// # Safety
+/// [in] `handle` must be a pointer returned by [`stream_create`] that was not
+/// finished yet.
+/// [in] `data` is the next chunk of the stream
+/// [in] `len` must be the number of bytes of `data`.
+/// [out] `res` will be filled with the offsets from the start of the stream,
+/// plus the base address, of all matches that end within this chunk
+/// [in] `res_len` is the amount of results that fit into `res`
+/// [return] returns how many matches end within this chunk. If that is more
+/// than `res_len`, only the first `res_len` were written. The stream moves on
+/// either way, so feed smaller chunks to get all of them.
+#[no_mangle]
+pub unsafe extern "C" fn stream_feed(
+    handle: *mut StreamHandle,
+    data: *const u8,
+    len: usize,
+    res: *mut u64,
+    res_len: usize,
+) -> usize {
+    guard(0, || {
+        if handle.is_null() || res.is_null() || data.is_null() {
+            return 0;
+        }
+        let data = slice::from_raw_parts(data, len);
+        let res = slice::from_raw_parts_mut(res, res_len);
+        // The stream carries over the end of the chunk even if not all results fit.
+        let handle = &mut *handle;
+        let base_address = handle.base_address;
+        let matches = handle.stream.feed(data);
+        fill(res, matches.map(|offset| base_address.wrapping_add(offset)))
+    })
+}
+
+/// # Safety
+/// [in] `handle` must be a pointer returned by [`stream_create`] or null. It
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn stream_finish(handle: *mut StreamHandle) {
+    guard((), || {
+        if !handle.is_null() {
+            drop(Box::from_raw(handle));
+        }
+    })
 }
 
 #[cfg(test)]
@@ -80,4 +555,226 @@ mod tests {
         assert_eq!(num_results, 1);
         assert_eq!(results[0], data.len() - 8);
     }
+
+    #[test]
+    pub fn scanner() {
+        let pattern = "01 02";
+        let mut data = vec![0u8; 1_000];
+        data[10..12].copy_from_slice(&[1, 2]);
+        data[500..502].copy_from_slice(&[1, 2]);
+        let mut res: Pattern = unsafe { core::mem::zeroed() };
+        let mut offsets = Vec::new();
+        unsafe {
+            parse_pattern(pattern.as_bytes().as_ptr(), pattern.len(), &mut res as _);
+            let handle = scanner_create(&res as _, data.as_ptr(), data.len(), 0x1000);
+            let mut offset = 0;
+            while scanner_next(handle, &mut offset) != 0 {
+                offsets.push(offset);
+            }
+            assert_eq!(scanner_next(handle, &mut offset), 0);
+            scanner_destroy(handle);
+        }
+        assert_eq!(offsets, [0x100a, 0x11f4]);
+    }
+
+    #[test]
+    pub fn callback() {
+        unsafe extern "C" fn first(offset: u64, user_data: *mut c_void) -> i32 {
+            *user_data.cast::<u64>() = offset;
+            0
+        }
+
+        let pattern = "01 02";
+        let mut data = vec![0u8; 1_000];
+        data[10..12].copy_from_slice(&[1, 2]);
+        data[500..502].copy_from_slice(&[1, 2]);
+        let mut res: Pattern = unsafe { core::mem::zeroed() };
+        let mut found = 0u64;
+        let calls = unsafe {
+            parse_pattern(pattern.as_bytes().as_ptr(), pattern.len(), &mut res as _);
+            match_pattern_cb(
+                &res as _,
+                data.as_ptr(),
+                data.len(),
+                0x1000,
+                Some(first),
+                (&mut found as *mut u64).cast(),
+            )
+        };
+        assert_eq!(calls, 1);
+        assert_eq!(found, 0x100a);
+    }
+
+    #[test]
+    pub fn multi() {
+        let mut data = vec![0u8; 1_000];
+        data[10..12].copy_from_slice(&[1, 2]);
+        data[500..502].copy_from_slice(&[3, 4]);
+        let mut patterns: [Pattern; 2] = unsafe { core::mem::zeroed() };
+        let mut results = [PatternMatch::default(); 4];
+        let found = unsafe {
+            for (pattern, res) in ["01 02", "03 04"].iter().zip(&mut patterns) {
+                parse_pattern(pattern.as_bytes().as_ptr(), pattern.len(), res as _);
+            }
+            match_patterns(
+                patterns.as_ptr(),
+                patterns.len(),
+                data.as_ptr(),
+                data.len(),
+                0,
+                results.as_mut_ptr(),
+                results.len(),
+            )
+        };
+        assert_eq!(found, 2);
+        let mut results = results[..found].to_vec();
+        results.sort_by_key(|found| found.offset);
+        assert_eq!(
+            results,
+            [
+                PatternMatch {
+                    offset: 10,
+                    length: 2,
+                    pattern_id: 0
+                },
+                PatternMatch {
+                    offset: 500,
+                    length: 2,
+                    pattern_id: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn stream() {
+        let pattern = "01 02 03";
+        let mut res: Pattern = unsafe { core::mem::zeroed() };
+        let mut results = [0u64; 4];
+        unsafe {
+            parse_pattern(pattern.as_bytes().as_ptr(), pattern.len(), &mut res as _);
+            let handle = stream_create(&res as _, 0x1000);
+            let first = [0, 1];
+            let found = stream_feed(handle, first.as_ptr(), first.len(), results.as_mut_ptr(), 4);
+            assert_eq!(found, 0);
+            let second = [2, 3, 1, 2, 3];
+            let found = stream_feed(
+                handle,
+                second.as_ptr(),
+                second.len(),
+                results.as_mut_ptr(),
+                4,
+            );
+            assert_eq!(results[..found], [0x1001, 0x1004]);
+            let third = [1, 2, 3, 1, 2, 3];
+            let found = stream_feed(handle, third.as_ptr(), third.len(), results.as_mut_ptr(), 1);
+            assert_eq!(found, 2);
+            assert_eq!(results[0], 0x1007);
+            stream_finish(handle);
+        }
+    }
+
+    #[test]
+    pub fn save_load() {
+        let pattern = "01 ? 03";
+        let mut res: Pattern = unsafe { core::mem::zeroed() };
+        let mut loaded: Pattern = unsafe { core::mem::zeroed() };
+        let mut buf = [0u8; 64];
+        unsafe {
+            parse_pattern(pattern.as_bytes().as_ptr(), pattern.len(), &mut res as _);
+            assert_eq!(pattern_save(&res as _, buf.as_mut_ptr(), 0), 14);
+            let len = pattern_save(&res as _, buf.as_mut_ptr(), buf.len());
+            assert_eq!(pattern_load(buf.as_ptr(), len, &mut loaded as _), 1);
+            assert_eq!(pattern_load(buf.as_ptr(), len - 1, &mut loaded as _), 0);
+        }
+        assert_eq!(loaded.bytes(), res.bytes());
+        assert_eq!(loaded.mask(), res.mask());
+        assert_eq!(loaded.find(&[0, 1, 2, 3]), Some(1));
+    }
+
+    #[test]
+    pub fn layout() {
+        assert_eq!(pattern_struct_size(), 256);
+        assert_eq!(pattern_struct_align(), 64);
+    }
+
+    #[test]
+    pub fn results() {
+        let pattern = "01 ? 03";
+        let data = [1, 2, 3, 1, 0, 3, 1, 1, 3];
+        let mut res: Pattern = unsafe { core::mem::zeroed() };
+        let mut results = [PatternMatch::default(); 2];
+        let found = unsafe {
+            parse_pattern(pattern.as_bytes().as_ptr(), pattern.len(), &mut res as _);
+            match_pattern_results(
+                &res as _,
+                data.as_ptr(),
+                data.len(),
+                0x1000,
+                results.as_mut_ptr(),
+                2,
+            )
+        };
+        assert_eq!(found, 3);
+        assert_eq!(results.map(|found| found.offset), [0x1000, 0x1003]);
+        assert_eq!(results[0].length, 3);
+    }
+
+    #[test]
+    pub fn status() {
+        let mut res: Pattern = unsafe { core::mem::zeroed() };
+        let parse = |pattern: &[u8], res: *mut Pattern| unsafe {
+            parse_pattern(pattern.as_ptr(), pattern.len(), res)
+        };
+        assert_eq!(parse(b"01 ?", &mut res), PatternStatus::Ok);
+        assert_eq!(parse(b"01 ?", ptr::null_mut()), PatternStatus::NullPointer);
+        assert_eq!(parse(b"\xff", &mut res), PatternStatus::InvalidUtf8);
+        assert_eq!(
+            parse(b"? ?", &mut res),
+            PatternStatus::MissingNonWildcardByte
+        );
+        assert_eq!(parse(b"0g", &mut res), PatternStatus::InvalidHexNumber);
+    }
+
+    /// Compares `include/patterns.h` with a freshly generated header. Run
+    /// with `PATTERNS_SYS_WRITE_HEADER` set to write it instead.
+    #[test]
+    pub fn header() {
+        let crate_dir = env!("CARGO_MANIFEST_DIR");
+        let path = std::path::Path::new(crate_dir).join("include/patterns.h");
+        let mut generated = Vec::new();
+        cbindgen::generate(crate_dir)
+            .expect("failed to generate the C header")
+            .write(&mut generated);
+        if std::env::var_os("PATTERNS_SYS_WRITE_HEADER").is_some() {
+            std::fs::write(&path, &generated).unwrap();
+        }
+        let header = std::fs::read(&path).unwrap();
+        assert!(
+            header == generated,
+            "include/patterns.h is outdated, run the tests with PATTERNS_SYS_WRITE_HEADER=1"
+        );
+        let version = format!("#define PATTERNS_ABI_VERSION {PATTERNS_ABI_VERSION}");
+        assert!(String::from_utf8(header).unwrap().contains(&version));
+    }
+
+    #[test]
+    pub fn owned() {
+        let data = [0, 1, 2, 3];
+        let mut status = PatternStatus::Panicked;
+        unsafe {
+            let pattern = pattern_new(b"? ?".as_ptr(), 3, &mut status);
+            assert!(pattern.is_null());
+            assert_eq!(status, PatternStatus::MissingNonWildcardByte);
+            let pattern = pattern_new(b"02 03".as_ptr(), 5, &mut status);
+            assert_eq!(status, PatternStatus::Ok);
+            let mut offset = 0;
+            assert_eq!(
+                match_pattern(pattern, data.as_ptr(), data.len(), &mut offset, 1),
+                1
+            );
+            assert_eq!(offset, 2);
+            pattern_free(pattern);
+        }
+    }
 }